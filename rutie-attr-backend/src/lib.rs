@@ -2,4 +2,20 @@ pub mod rutie_attr_backend;
 
 pub use rutie_attr_backend::arg::{Arg, FromArg};
 pub use rutie_attr_backend::arg_with_default_value::{DArg, FromArgWithDefault};
+pub use rutie_attr_backend::argument_list::{arguments, ArgumentList};
+pub use rutie_attr_backend::argument_name::ArgumentName;
+pub use rutie_attr_backend::block::{yield_block, Block, BlockArgs, BlockError};
+pub use rutie_attr_backend::block_arg::{BlockArg, FromBlockArg};
+pub use rutie_attr_backend::class_arg::convert_class;
+pub use rutie_attr_backend::coerce::{Coerce, ImplicitConversion};
+pub use rutie_attr_backend::default_value::{constant_value, ToDefaultValue};
+pub use rutie_attr_backend::double_splat_arg::{DoubleSplatArg, FromDoubleSplatArg};
+pub use rutie_attr_backend::element::no_implicit_conversion;
+pub use rutie_attr_backend::from_ruby::FromRuby;
+pub use rutie_attr_backend::instance_variable::InstanceVariable;
 pub use rutie_attr_backend::keyword_arg::{KwArg, FromArgWithKeyAndDefault};
+pub use rutie_attr_backend::keyword_name::KeywordName;
+pub use rutie_attr_backend::map::{FromHashKey, HashKey};
+pub use rutie_attr_backend::method_definition::{define_method, define_singleton_method, set_visibility};
+pub use rutie_attr_backend::return_value::{allocate, IntoRuby};
+pub use rutie_attr_backend::splat_arg::{SplatArg, FromSplatArg};
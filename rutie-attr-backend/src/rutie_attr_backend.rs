@@ -1,3 +1,20 @@
 pub mod arg;
 pub mod arg_with_default_value;
+pub mod argument_list;
+pub mod argument_name;
+pub mod block;
+pub mod block_arg;
+pub mod class_arg;
+pub mod coerce;
+pub mod default_value;
+pub mod double_splat_arg;
+pub mod element;
+mod ffi;
+pub mod from_ruby;
+pub mod instance_variable;
 pub mod keyword_arg;
+pub mod keyword_name;
+pub mod map;
+pub mod method_definition;
+pub mod return_value;
+pub mod splat_arg;
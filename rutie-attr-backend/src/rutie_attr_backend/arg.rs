@@ -1,32 +1,36 @@
-use rutie::{Boolean, Fixnum, Float, Hash, Integer, NilClass, RString, Symbol, AnyException, AnyObject, Exception, Object};
+use rutie::{AnyException, AnyObject, Exception};
+use super::argument_name::ArgumentName;
+use super::from_ruby::FromRuby;
 
 pub struct Arg<T> {
     pub result: Result<T, AnyException>,
 }
 
 pub trait FromArg<T>: Sized {
-    fn from_arg(from: Option<&AnyObject>) -> Self;
+    /// methodはエラーメッセージに使うRubyでのメソッド名 ("Foo#bar")
+    fn from_arg(from: Option<&AnyObject>, name: &str, method: &str) -> Self;
 }
 
-macro_rules! impl_from_arg {
-    ($($struct_name:ty),*) => ($(
-        impl FromArg<$struct_name> for Arg<$struct_name> {
-            fn from_arg(from: Option<&AnyObject>) -> Arg<$struct_name> {
-                let result = if let Some(o) = from {
-                    o.try_convert_to::<$struct_name>()
-                } else {
-                    Err(AnyException::new("ArgumentError", Some("missing argument")))
-                };
-                Arg { result }
-            }
-        }
+// 位置引数が渡されていないときのArgumentError
+pub(crate) fn missing_argument(argument: &ArgumentName) -> AnyException {
+    let message = format!("missing {}", argument);
+    AnyException::new("ArgumentError", Some(&message))
+}
 
-        impl Into<$struct_name> for Arg<$struct_name> {
-            fn into(self) -> $struct_name {
-                self.result.as_ref().ok().unwrap().value().clone().into()
-            }
-        }
-    )*)
+impl<T: FromRuby> FromArg<T> for Arg<T> {
+    fn from_arg(from: Option<&AnyObject>, name: &str, method: &str) -> Arg<T> {
+        let argument = ArgumentName::new(name, method);
+        let result = match from {
+            Some(o) => T::from_ruby(o, &argument),
+            None => T::from_missing().ok_or_else(|| missing_argument(&argument)),
+        };
+        Arg { result }
+    }
 }
 
-impl_from_arg!(Boolean, Fixnum, Float, Hash, Integer, NilClass, RString, Symbol);
+impl<T> Arg<T> {
+    /// exceptionを確認してから呼ぶ
+    pub fn into_value(self) -> T {
+        self.result.ok().unwrap()
+    }
+}
@@ -1,36 +1,33 @@
-use rutie::{Boolean, Fixnum, Float, Hash, Integer, NilClass, RString, Symbol, AnyException, AnyObject, Exception, Object};
+use rutie::{AnyException, AnyObject};
+use super::argument_name::ArgumentName;
+use super::from_ruby::FromRuby;
 
 pub struct DArg<T> {
     pub result: Result<T, AnyException>,
 }
 
 pub trait FromArgWithDefault<T>: Sized {
-    fn from_arg_with_default(from: Option<&AnyObject>, default_value: Option<&AnyObject>) -> Self;
+    /// default_valueは引数が省略されたときだけ呼ぶ。デフォルト値を作れなかったときのエラーはresultに入れる
+    fn from_arg_with_default<F: FnOnce() -> Result<AnyObject, AnyException>>(from: Option<&AnyObject>, name: &str, method: &str, default_value: F) -> Self;
 }
 
-macro_rules! impl_from_arg_with_default {
-    ($($struct_name:ty),*) => ($(
-        impl FromArgWithDefault<$struct_name> for DArg<$struct_name> {
-            fn from_arg_with_default(from: Option<&AnyObject>, default_value: Option<&AnyObject>) -> DArg<$struct_name> {
-                let result = if let Some(o) = from {
-                    o.try_convert_to::<$struct_name>()
-                } else {
-                    if let Some(o) = default_value {
-                        o.try_convert_to::<$struct_name>()
-                    } else {
-                        Err(AnyException::new("ArgumentError", Some("missing argument")))
-                    }
-                };
-                DArg { result }
-            }
-        }
+// 引数が省略されたときだけデフォルト値を作る
+fn argument_or_default<F: FnOnce() -> Result<AnyObject, AnyException>>(from: Option<&AnyObject>, default_value: F) -> Result<AnyObject, AnyException> {
+    match from {
+        Some(o) => Ok(o.clone()),
+        None => default_value(),
+    }
+}
 
-        impl Into<$struct_name> for DArg<$struct_name> {
-            fn into(self) -> $struct_name {
-                self.result.as_ref().ok().unwrap().value().clone().into()
-            }
-        }
-    )*)
+impl<T: FromRuby> FromArgWithDefault<T> for DArg<T> {
+    fn from_arg_with_default<F: FnOnce() -> Result<AnyObject, AnyException>>(from: Option<&AnyObject>, name: &str, method: &str, default_value: F) -> DArg<T> {
+        let result = argument_or_default(from, default_value).and_then(|o| T::from_ruby(&o, &ArgumentName::new(name, method)));
+        DArg { result }
+    }
 }
 
-impl_from_arg_with_default!(Boolean, Fixnum, Float, Hash, Integer, NilClass, RString, Symbol);
+impl<T> DArg<T> {
+    pub fn into_value(self) -> T {
+        self.result.ok().unwrap()
+    }
+}
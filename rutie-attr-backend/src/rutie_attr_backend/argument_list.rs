@@ -0,0 +1,201 @@
+use rutie::{AnyException, AnyObject, Exception, Hash, Object, Proc, RString, VM};
+use rutie::types::Argc;
+use super::ffi::rb_keyword_given_p;
+use super::keyword_name::KeywordName;
+
+// 呼び出し元がキーワード引数を渡したかどうか
+fn keyword_given() -> bool {
+    unsafe { rb_keyword_given_p() != 0 }
+}
+
+/// Rubyから渡されたargc, argvをスライスにする
+///
+/// `rutie::util::parse_arguments` と違ってVecにコピーしない
+///
+/// # Safety
+///
+/// `argv` はRubyから渡された `argc` 個の `AnyObject` を指していて、返したスライスを使う間は有効であること
+pub unsafe fn arguments<'a>(argc: Argc, argv: *const AnyObject) -> &'a [AnyObject] {
+    if argc <= 0 || argv.is_null() {
+        &[]
+    } else {
+        std::slice::from_raw_parts(argv, argc as usize)
+    }
+}
+
+/// Rubyから渡された引数を、Rubyと同じ規則で必須・省略可能・可変長・後置・キーワード引数に振り分ける
+///
+/// `def foo(a, b = 1, *rest, c, d: 2)` の場合は `ArgumentList::new(unsafe { arguments(argc, argv) }, 1, 1, 1, true)`
+pub struct ArgumentList<'a> {
+    arguments: &'a [AnyObject],
+    keywords: Option<&'a AnyObject>,
+    required: usize,
+    optional: usize,
+    post: usize,
+}
+
+impl<'a> ArgumentList<'a> {
+    pub fn new(arguments: &'a [AnyObject], required: usize, optional: usize, post: usize, has_keywords: bool) -> Self {
+        // Ruby 3と同じく、キーワード引数として渡されたときだけ最後のHashをキーワード引数として扱う
+        // 位置引数として渡されたHashはそのまま位置引数にする
+        match arguments.split_last() {
+            Some((keywords, arguments)) if has_keywords && keyword_given() => {
+                Self { arguments, keywords: Some(keywords), required, optional, post }
+            },
+            _ => Self { arguments, keywords: None, required, optional, post },
+        }
+    }
+
+    // 省略可能な引数に割り当てられる個数
+    fn given_optional(&self) -> usize {
+        self.arguments
+            .len()
+            .saturating_sub(self.required + self.post)
+            .min(self.optional)
+    }
+
+    // 後置の必須引数の開始位置
+    fn post_start(&self) -> usize {
+        self.arguments
+            .len()
+            .saturating_sub(self.post)
+            .max(self.required + self.given_optional())
+    }
+
+    pub fn len(&self) -> usize {
+        self.arguments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.arguments.is_empty()
+    }
+
+    pub fn required(&self, index: usize) -> Option<&AnyObject> {
+        if index < self.required {
+            self.arguments.get(index)
+        } else {
+            None
+        }
+    }
+
+    pub fn optional(&self, index: usize) -> Option<&AnyObject> {
+        if index < self.given_optional() {
+            self.arguments.get(self.required + index)
+        } else {
+            None
+        }
+    }
+
+    pub fn splat(&self) -> &[AnyObject] {
+        // 必須引数が足りないときは空にする
+        let end = self.post_start().min(self.arguments.len());
+        let start = (self.required + self.given_optional()).min(end);
+        &self.arguments[start..end]
+    }
+
+    pub fn post(&self, index: usize) -> Option<&AnyObject> {
+        if index < self.post {
+            self.arguments.get(self.post_start() + index)
+        } else {
+            None
+        }
+    }
+
+    pub fn keywords(&self) -> Option<&AnyObject> {
+        self.keywords
+    }
+
+    // 位置引数の個数がminimum..=maximumに収まらないときはRubyと同じArgumentErrorにする
+    // maximumがNoneのときは *rest があるので上限なし
+    // 必須のキーワード引数があるときは、Rubyと同じくメッセージの末尾に列挙する
+    pub fn arity(&self, minimum: usize, maximum: Option<usize>, required_keywords: &[&KeywordName]) -> Result<(), AnyException> {
+        let given = self.arguments.len();
+        if given >= minimum && maximum.is_none_or(|maximum| given <= maximum) {
+            return Ok(());
+        }
+        let expected = match maximum {
+            Some(maximum) if maximum == minimum => format!("{}", minimum),
+            Some(maximum) => format!("{}..{}", minimum, maximum),
+            None => format!("{}+", minimum),
+        };
+        let names = required_keywords.iter().map(|key| key.name()).collect::<Vec<&str>>();
+        let required_keywords = match names.len() {
+            0 => String::new(),
+            1 => format!("; required keyword: {}", names[0]),
+            _ => format!("; required keywords: {}", names.join(", ")),
+        };
+        let message = format!("wrong number of arguments (given {}, expected {}{})", given, expected, required_keywords);
+        Err(AnyException::new("ArgumentError", Some(&message)))
+    }
+
+    // 必須のキーワード引数が渡されていないときはRubyと同じArgumentErrorにする
+    pub fn missing_keywords(&self, required: &[&KeywordName]) -> Result<(), AnyException> {
+        let is_missing = |key: &KeywordName| self.keywords.and_then(|hash| key.lookup(hash)).is_none();
+        if !required.iter().any(|key| is_missing(key)) {
+            return Ok(());
+        }
+        let missing = required
+            .iter()
+            .filter(|key| is_missing(key))
+            .map(|key| format!(":{}", key.name()))
+            .collect::<Vec<String>>();
+        match missing.len() {
+            1 => Err(AnyException::new("ArgumentError", Some(&format!("missing keyword: {}", missing[0])))),
+            _ => Err(AnyException::new("ArgumentError", Some(&format!("missing keywords: {}", missing.join(", "))))),
+        }
+    }
+
+    // ブロックは使うときだけProcにする
+    pub fn block(&self) -> Option<Proc> {
+        if VM::is_block_given() {
+            Some(VM::block_proc())
+        } else {
+            None
+        }
+    }
+
+    // キーワード引数のうち、knownに含まれないものだけを集めたHash
+    pub fn rest_keywords(&self, known: &[&KeywordName]) -> AnyObject {
+        let mut rest = Hash::new();
+        self.each_unknown_keyword(known, |key, value| {
+            rest.store(key, value);
+        });
+        rest.to_any_object()
+    }
+
+    // **opts が無いメソッドに知らないキーワードが渡されたときはRubyと同じArgumentErrorにする
+    pub fn unknown_keywords(&self, known: &[&KeywordName]) -> Result<(), AnyException> {
+        // 渡されたキーワードがすべて知っているキーワードなら、Hashを走査しない
+        let hash = match self.keywords.and_then(|o| o.try_convert_to::<Hash>().ok()) {
+            Some(hash) => hash,
+            None => return Ok(()),
+        };
+        let given = known.iter().filter(|key| key.lookup(self.keywords.unwrap()).is_some()).count();
+        if hash.length() == given {
+            return Ok(());
+        }
+        let mut unknown = Vec::new();
+        self.each_unknown_keyword(known, |key, _| {
+            let inspect = unsafe { key.send("inspect", &[]) };
+            unknown.push(inspect.try_convert_to::<RString>().map(|s| s.to_string()).unwrap_or_default());
+        });
+        match unknown.len() {
+            0 => Ok(()),
+            1 => Err(AnyException::new("ArgumentError", Some(&format!("unknown keyword: {}", unknown[0])))),
+            _ => Err(AnyException::new("ArgumentError", Some(&format!("unknown keywords: {}", unknown.join(", "))))),
+        }
+    }
+
+    fn each_unknown_keyword<F>(&self, known: &[&KeywordName], mut f: F)
+    where
+        F: FnMut(AnyObject, AnyObject),
+    {
+        if let Some(hash) = self.keywords.and_then(|o| o.try_convert_to::<Hash>().ok()) {
+            hash.each(|key, value| {
+                if !known.iter().any(|k| k.is(&key)) {
+                    f(key, value);
+                }
+            });
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use rutie::{AnyObject, Object, RString};
+use std::fmt;
+
+/// エラーメッセージに使う引数の説明
+///
+/// 変換に失敗したときだけ "argument 'name' of Foo#bar" の形にformatする。
+/// ArrayやHashの中身は "element 0 of argument 'name' of Foo#bar" のように親の説明をつなげる
+#[derive(Debug, Clone, Copy)]
+pub struct ArgumentName<'a> {
+    kind: Kind<'a>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Kind<'a> {
+    Argument { name: &'a str, method: &'a str },
+    Keyword { name: &'a str, method: &'a str },
+    // Arrayの要素
+    Element { index: usize, parent: &'a ArgumentName<'a> },
+    // Hashのキーと、キーに対応する値
+    Key { key: &'a AnyObject, parent: &'a ArgumentName<'a> },
+    Value { key: &'a AnyObject, parent: &'a ArgumentName<'a> },
+    // ブロックの戻り値
+    BlockResult,
+}
+
+impl<'a> ArgumentName<'a> {
+    /// methodは "Foo#bar" や "Foo.bar" のようなRubyでのメソッド名
+    pub const fn new(name: &'a str, method: &'a str) -> Self {
+        Self { kind: Kind::Argument { name, method } }
+    }
+
+    pub const fn keyword(name: &'a str, method: &'a str) -> Self {
+        Self { kind: Kind::Keyword { name, method } }
+    }
+
+    pub const fn block_result() -> Self {
+        Self { kind: Kind::BlockResult }
+    }
+
+    pub fn element(&'a self, index: usize) -> Self {
+        Self { kind: Kind::Element { index, parent: self } }
+    }
+
+    pub fn key(&'a self, key: &'a AnyObject) -> Self {
+        Self { kind: Kind::Key { key, parent: self } }
+    }
+
+    pub fn value(&'a self, key: &'a AnyObject) -> Self {
+        Self { kind: Kind::Value { key, parent: self } }
+    }
+}
+
+// キーの表示はエラーメッセージを作るときだけinspectする
+fn inspect(key: &AnyObject) -> String {
+    key.protect_send("inspect", &[])
+        .ok()
+        .and_then(|inspect| inspect.try_convert_to::<RString>().ok())
+        .map(|s| s.to_string())
+        .unwrap_or_default()
+}
+
+fn write_method(f: &mut fmt::Formatter<'_>, method: &str) -> fmt::Result {
+    if method.is_empty() {
+        Ok(())
+    } else {
+        write!(f, " of {}", method)
+    }
+}
+
+impl fmt::Display for ArgumentName<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            Kind::Argument { name, method } => {
+                write!(f, "argument '{}'", name)?;
+                write_method(f, method)
+            },
+            Kind::Keyword { name, method } => {
+                write!(f, "keyword '{}'", name)?;
+                write_method(f, method)
+            },
+            Kind::Element { index, parent } => write!(f, "element {} of {}", index, parent),
+            Kind::Key { key, parent } => write!(f, "key {} of {}", inspect(key), parent),
+            Kind::Value { key, parent } => write!(f, "value at key {} of {}", inspect(key), parent),
+            Kind::BlockResult => write!(f, "block result"),
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use rutie::types::c_int;
+use rutie::{AnyException, AnyObject, Exception, Object, Proc, VM};
+use std::marker::PhantomData;
+use super::argument_name::ArgumentName;
+use super::ffi::rb_jump_tag;
+use super::from_ruby::FromRuby;
+
+/// ブロックに渡す引数。`()` と要素が4つまでのタプルで渡せる
+pub trait BlockArgs {
+    fn into_arguments(self) -> Vec<AnyObject>;
+}
+
+impl BlockArgs for () {
+    fn into_arguments(self) -> Vec<AnyObject> {
+        Vec::new()
+    }
+}
+
+macro_rules! impl_block_args {
+    ($(($($name:ident),*)),*) => ($(
+        impl<$($name: Object),*> BlockArgs for ($($name,)*) {
+            #[allow(non_snake_case)]
+            fn into_arguments(self) -> Vec<AnyObject> {
+                let ($($name,)*) = self;
+                vec![$($name.to_any_object()),*]
+            }
+        }
+    )*)
+}
+
+impl_block_args!((A), (A, B), (A, B, C), (A, B, C, D));
+
+/// ブロックを呼び出したときのエラー
+///
+/// rbdefの関数から `Result<T, BlockError>` で返すと、Rustの値をすべてdropしてから
+/// 例外をraiseするか、ブロックからのbreakやthrowを続ける
+pub enum BlockError {
+    /// ブロック内で起きた例外
+    Exception(AnyException),
+    /// break, throw, return のような例外以外でブロックを抜けたときの、rb_protectのstate
+    Jump(c_int),
+}
+
+impl BlockError {
+    /// 例外をraiseするか、rb_jump_tagで中断した大域脱出を続ける
+    ///
+    /// 戻ってこないので、Rustの値をすべてdropしてから呼ぶ
+    pub fn resume(self) {
+        match self {
+            Self::Exception(e) => VM::raise_ex(e),
+            Self::Jump(state) => unsafe { rb_jump_tag(state) },
+        }
+    }
+}
+
+impl From<AnyException> for BlockError {
+    fn from(e: AnyException) -> Self {
+        Self::Exception(e)
+    }
+}
+
+/// 引数と戻り値の型を決めたブロック
+///
+/// `fn each(&self, blk: Block<(Fixnum,), i64>)` のように受け取って `blk.call((Fixnum::new(1),))` で呼び出す
+pub struct Block<A, R = AnyObject> {
+    proc: Proc,
+    _marker: PhantomData<fn(A) -> R>,
+}
+
+impl<A: BlockArgs, R: FromRuby> Block<A, R> {
+    pub fn new(proc: Proc) -> Self {
+        Self { proc, _marker: PhantomData }
+    }
+
+    /// ブロック内で起きた例外やbreakは `Err` で返す
+    pub fn call(&self, args: A) -> Result<R, BlockError> {
+        let arguments = args.into_arguments();
+        let result = protect(|| self.proc.call(&arguments))?;
+        Ok(convert_block_result(&result)?)
+    }
+
+    pub fn to_proc(&self) -> Proc {
+        Proc::from(self.proc.value())
+    }
+}
+
+// rb_protectのstateのうち、例外がraiseされたときの値 (RubyのTAG_RAISE)
+const TAG_RAISE: c_int = 6;
+
+// ブロック内の例外やbreakがRustのスタックを飛び越えないように、rb_protectの中で呼び出す
+fn protect<F: FnMut() -> AnyObject>(func: F) -> Result<AnyObject, BlockError> {
+    VM::protect(func).map_err(|state| {
+        if state == TAG_RAISE {
+            BlockError::Exception(VM::error_pop().unwrap_or_else(|_| AnyException::new("RuntimeError", Some("unknown error in the block"))))
+        } else {
+            // breakやthrowのエラー情報はrb_jump_tagで続けるときに使うので、消さずに残す
+            BlockError::Jump(state)
+        }
+    })
+}
+
+fn convert_block_result<R: FromRuby>(result: &AnyObject) -> Result<R, AnyException> {
+    R::from_ruby(result, &ArgumentName::block_result())
+}
+
+pub fn no_block_given() -> AnyException {
+    AnyException::new("LocalJumpError", Some("no block given (yield)"))
+}
+
+/// 呼び出し中のメソッドに渡されたブロックをyieldする
+///
+/// ブロックが渡されていないときはRubyと同じLocalJumpErrorを返す。ブロック内で起きた例外やbreakも `Err` で返す
+pub fn yield_block<A: BlockArgs, R: FromRuby>(args: A) -> Result<R, BlockError> {
+    if !VM::is_block_given() {
+        return Err(no_block_given().into());
+    }
+    let arguments = args.into_arguments();
+    let result = protect(|| match arguments.len() {
+        1 => VM::yield_object(arguments[0].clone()),
+        _ => VM::yield_splat(arguments.iter().cloned().collect()),
+    })?;
+    Ok(convert_block_result(&result)?)
+}
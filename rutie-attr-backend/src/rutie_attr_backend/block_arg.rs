@@ -0,0 +1,45 @@
+use rutie::{AnyException, Proc};
+use super::block::{no_block_given, Block, BlockArgs};
+use super::from_ruby::FromRuby;
+
+pub struct BlockArg<T> {
+    pub result: Result<T, AnyException>,
+}
+
+pub trait FromBlockArg<T>: Sized {
+    fn from_block_arg(from: Option<Proc>, name: &str) -> Self;
+}
+
+// Option以外で受け取るブロックは必須
+impl FromBlockArg<Proc> for BlockArg<Proc> {
+    fn from_block_arg(from: Option<Proc>, _name: &str) -> BlockArg<Proc> {
+        let result = from.ok_or_else(no_block_given);
+        BlockArg { result }
+    }
+}
+
+impl<A: BlockArgs, R: FromRuby> FromBlockArg<Block<A, R>> for BlockArg<Block<A, R>> {
+    fn from_block_arg(from: Option<Proc>, _name: &str) -> BlockArg<Block<A, R>> {
+        let result = from.map(Block::new).ok_or_else(no_block_given);
+        BlockArg { result }
+    }
+}
+
+impl<T> FromBlockArg<Option<T>> for BlockArg<Option<T>>
+where
+    BlockArg<T>: FromBlockArg<T>,
+{
+    fn from_block_arg(from: Option<Proc>, name: &str) -> BlockArg<Option<T>> {
+        let result = match from {
+            Some(p) => BlockArg::<T>::from_block_arg(Some(p), name).result.map(Some),
+            None => Ok(None),
+        };
+        BlockArg { result }
+    }
+}
+
+impl<T> BlockArg<T> {
+    pub fn into_value(self) -> T {
+        self.result.ok().unwrap()
+    }
+}
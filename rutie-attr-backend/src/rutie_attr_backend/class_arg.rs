@@ -0,0 +1,23 @@
+use rutie::types::Value;
+use rutie::{AnyException, AnyObject, Class, Exception, Object};
+use std::convert::TryFrom;
+use super::argument_name::ArgumentName;
+use super::element::class_name;
+use super::ffi::rb_obj_is_kind_of;
+
+/// rbclassの構造体を引数で受け取る
+///
+/// Rubyのクラスのインスタンスかチェックしてから、TryFromで構造体に変換する
+/// 違うクラスのときは "wrong argument type Integer (expected Point) for argument 'other' of Point#distance" のTypeError
+pub fn convert_class<T, R>(from: &AnyObject, class: &str, argument: &ArgumentName) -> Result<T, AnyException>
+where
+    T: TryFrom<R, Error = AnyException>,
+    R: From<Value>,
+{
+    let expected = Class::from_existing(class);
+    if !unsafe { rb_obj_is_kind_of(from.value(), expected.value()) }.is_true() {
+        let message = format!("wrong argument type {} (expected {}) for {}", class_name(from), class, argument);
+        return Err(AnyException::new("TypeError", Some(&message)));
+    }
+    T::try_from(R::from(from.value()))
+}
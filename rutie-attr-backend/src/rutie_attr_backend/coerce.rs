@@ -0,0 +1,101 @@
+use rutie::{Array, Fixnum, Hash, Integer, RString, AnyException, AnyObject, Exception, Object};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::ops::Deref;
+use super::argument_name::ArgumentName;
+use super::element::class_name;
+use super::from_ruby::FromRuby;
+use super::map::FromHashKey;
+
+/// Rubyの暗黙の型変換 (to_str, to_int, to_ary, to_hash) を使って受け取る引数
+///
+/// `fn open(path: Coerce<RString>)` のように書くと、Pathnameのようにto_strを持つオブジェクトも受け取れる
+pub struct Coerce<T>(pub T);
+
+/// 暗黙の型変換のメソッド名
+pub trait ImplicitConversion: FromRuby {
+    const METHOD: &'static str;
+    /// エラーメッセージ用のRubyのクラス名
+    const CLASS: &'static str;
+}
+
+macro_rules! impl_implicit_conversion {
+    ($($struct_name:ty => $method:expr, $class:expr),*) => ($(
+        impl ImplicitConversion for $struct_name {
+            const METHOD: &'static str = $method;
+            const CLASS: &'static str = $class;
+        }
+    )*)
+}
+
+impl_implicit_conversion!(
+    RString => "to_str", "String",
+    Fixnum => "to_int", "Integer",
+    Integer => "to_int", "Integer",
+    Array => "to_ary", "Array",
+    Hash => "to_hash", "Hash"
+);
+
+impl<T: FromRuby> ImplicitConversion for Vec<T> {
+    const METHOD: &'static str = "to_ary";
+    const CLASS: &'static str = "Array";
+}
+
+impl<K, T> ImplicitConversion for HashMap<K, T>
+where
+    K: FromHashKey + Eq + std::hash::Hash + fmt::Debug,
+    T: FromRuby,
+{
+    const METHOD: &'static str = "to_hash";
+    const CLASS: &'static str = "Hash";
+}
+
+impl<K, T> ImplicitConversion for BTreeMap<K, T>
+where
+    K: FromHashKey + Ord + fmt::Debug,
+    T: FromRuby,
+{
+    const METHOD: &'static str = "to_hash";
+    const CLASS: &'static str = "Hash";
+}
+
+// そのまま変換できないときだけ暗黙の型変換のメソッドを呼ぶ
+impl<T: ImplicitConversion> FromRuby for Coerce<T> {
+    fn from_ruby(from: &AnyObject, argument: &ArgumentName) -> Result<Self, AnyException> {
+        let error = match T::from_ruby(from, argument) {
+            Ok(value) => return Ok(Coerce(value)),
+            Err(e) => e,
+        };
+        if from.is_nil() || !from.respond_to(T::METHOD) {
+            return Err(error);
+        }
+        let converted = from.protect_send(T::METHOD, &[])?;
+        // Rubyと同じく、変換した結果が違う型のときもTypeError
+        T::from_ruby(&converted, argument).map(Coerce).map_err(|_| {
+            let message = format!(
+                "can't convert {} to {} ({}#{} gives {}) for {}",
+                class_name(from),
+                T::CLASS,
+                class_name(from),
+                T::METHOD,
+                class_name(&converted),
+                argument,
+            );
+            AnyException::new("TypeError", Some(&message))
+        })
+    }
+}
+
+impl<T> Coerce<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Coerce<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
@@ -0,0 +1,89 @@
+use rutie::{AnyException, AnyObject, Array, Boolean, Class, Float, Integer, NilClass, Object, RString};
+
+/// rbdefのデフォルト値に `rust!(expr)` で書いたRustの値をRubyのオブジェクトにする
+pub trait ToDefaultValue {
+    fn to_default_value(&self) -> AnyObject;
+}
+
+macro_rules! impl_to_default_value_for_integer {
+    ($($t:ty),*) => ($(
+        impl ToDefaultValue for $t {
+            fn to_default_value(&self) -> AnyObject {
+                Integer::new(*self as i64).to_any_object()
+            }
+        }
+    )*)
+}
+
+impl_to_default_value_for_integer!(i8, i16, i32, i64, isize, u8, u16, u32);
+
+impl ToDefaultValue for u64 {
+    fn to_default_value(&self) -> AnyObject {
+        Integer::from(*self).to_any_object()
+    }
+}
+
+impl ToDefaultValue for f32 {
+    fn to_default_value(&self) -> AnyObject {
+        Float::new(f64::from(*self)).to_any_object()
+    }
+}
+
+impl ToDefaultValue for f64 {
+    fn to_default_value(&self) -> AnyObject {
+        Float::new(*self).to_any_object()
+    }
+}
+
+impl ToDefaultValue for bool {
+    fn to_default_value(&self) -> AnyObject {
+        Boolean::new(*self).to_any_object()
+    }
+}
+
+impl ToDefaultValue for str {
+    fn to_default_value(&self) -> AnyObject {
+        RString::new_utf8(self).to_any_object()
+    }
+}
+
+impl ToDefaultValue for String {
+    fn to_default_value(&self) -> AnyObject {
+        self.as_str().to_default_value()
+    }
+}
+
+impl<T: ToDefaultValue> ToDefaultValue for Option<T> {
+    fn to_default_value(&self) -> AnyObject {
+        match self {
+            Some(v) => v.to_default_value(),
+            None => NilClass::new().to_any_object(),
+        }
+    }
+}
+
+impl<T: ToDefaultValue> ToDefaultValue for [T] {
+    fn to_default_value(&self) -> AnyObject {
+        self.iter().map(|v| v.to_default_value()).collect::<Array>().to_any_object()
+    }
+}
+
+impl<T: ToDefaultValue> ToDefaultValue for Vec<T> {
+    fn to_default_value(&self) -> AnyObject {
+        self.as_slice().to_default_value()
+    }
+}
+
+impl<T: ToDefaultValue + ?Sized> ToDefaultValue for &T {
+    fn to_default_value(&self) -> AnyObject {
+        (**self).to_default_value()
+    }
+}
+
+/// `LIMIT` や `Float::INFINITY` のようなRubyの定数の値を取得する
+///
+/// メソッドを定義したクラスの定数から探し、無ければ継承元とトップレベルの定数を探す。
+/// 定数が無いときはraiseせずにNameErrorを返す
+pub fn constant_value(class_name: &str, name: &str) -> Result<AnyObject, AnyException> {
+    Class::from_existing(class_name).protect_send("const_get", &[RString::new_utf8(name).to_any_object()])
+}
@@ -0,0 +1,24 @@
+use rutie::{AnyException, AnyObject};
+use super::argument_name::ArgumentName;
+use super::from_ruby::FromRuby;
+
+pub struct DoubleSplatArg<T> {
+    pub result: Result<T, AnyException>,
+}
+
+pub trait FromDoubleSplatArg<T>: Sized {
+    fn from_double_splat_arg(from: AnyObject, name: &str, method: &str) -> Self;
+}
+
+impl<T: FromRuby> FromDoubleSplatArg<T> for DoubleSplatArg<T> {
+    fn from_double_splat_arg(from: AnyObject, name: &str, method: &str) -> DoubleSplatArg<T> {
+        let result = T::from_ruby(&from, &ArgumentName::new(name, method));
+        DoubleSplatArg { result }
+    }
+}
+
+impl<T> DoubleSplatArg<T> {
+    pub fn into_value(self) -> T {
+        self.result.ok().unwrap()
+    }
+}
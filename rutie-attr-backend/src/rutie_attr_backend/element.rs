@@ -0,0 +1,49 @@
+use rutie::{Array, Boolean, RString, AnyException, AnyObject, Exception, Object};
+use super::argument_name::ArgumentName;
+use super::from_ruby::FromRuby;
+
+// オブジェクトのクラス名を取得する。無名クラスはinspectした名前
+pub fn class_name(object: &AnyObject) -> String {
+    let class = object.class();
+    let name = unsafe { class.send("name", &[]) };
+    let name = if name.is_nil() { unsafe { class.send("inspect", &[]) } } else { name };
+    name.try_convert_to::<RString>()
+        .map(|s| s.to_string())
+        .unwrap_or_default()
+}
+
+// Rubyと同じく、nil, true, falseはクラス名ではなく値で表す
+pub fn no_implicit_conversion(from: &AnyObject, expected: &str) -> String {
+    let actual = if from.is_nil() {
+        String::from("nil")
+    } else if let Ok(b) = from.try_convert_to::<Boolean>() {
+        b.to_bool().to_string()
+    } else {
+        class_name(from)
+    };
+    format!("no implicit conversion of {} into {}", actual, expected)
+}
+
+// 要素ごとにVec<T>に変換する。失敗したときは引数と要素のindexをメッセージに含める
+pub fn convert_elements<T, I>(elements: I, argument: &ArgumentName) -> Result<Vec<T>, AnyException>
+where
+    T: FromRuby,
+    I: IntoIterator<Item = AnyObject>,
+{
+    elements
+        .into_iter()
+        .enumerate()
+        .map(|(i, o)| T::from_ruby(&o, &argument.element(i)))
+        .collect()
+}
+
+// AnyObjectをArrayに変換してからVec<T>に変換する
+pub fn convert_vec<T: FromRuby>(from: &AnyObject, argument: &ArgumentName) -> Result<Vec<T>, AnyException> {
+    match from.try_convert_to::<Array>() {
+        Ok(array) => convert_elements(array, argument),
+        Err(_) => {
+            let message = format!("{} for {}", no_implicit_conversion(from, "Array"), argument);
+            Err(AnyException::new("TypeError", Some(&message)))
+        },
+    }
+}
@@ -0,0 +1,14 @@
+use rutie::types::{c_char, c_int, c_long, CallbackPtr, EncodingType, Id, Value};
+
+// rutieにバインディングが無いRubyのC APIを直接宣言する
+extern "C" {
+    // Ruby 2.7以降
+    pub fn rb_keyword_given_p() -> c_int;
+    pub fn rb_hash_lookup2(hash: Value, key: Value, default: Value) -> Value;
+    pub fn rb_obj_is_kind_of(object: Value, class: Value) -> Value;
+    pub fn rb_utf8_encoding() -> EncodingType;
+    pub fn rb_intern3(name: *const c_char, len: c_long, encoding: EncodingType) -> Id;
+    pub fn rb_define_method_id(klass: Value, id: Id, callback: CallbackPtr, arity: c_int);
+    // rb_protectで止めた例外や大域脱出を続ける
+    pub fn rb_jump_tag(state: c_int) -> !;
+}
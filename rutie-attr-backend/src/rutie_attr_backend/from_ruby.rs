@@ -0,0 +1,210 @@
+use rutie::{Array, Boolean, Fixnum, Float, Hash, Integer, NilClass, RString, Symbol, AnyException, AnyObject, Exception, Object, VM};
+use std::convert::TryFrom;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use super::argument_name::ArgumentName;
+use super::element::convert_vec;
+use super::map::{convert_btree_map, convert_hash_map, FromHashKey};
+
+/// 引数で受け取るRustの型への変換
+///
+/// Arg, DArg, KwArgはこのtraitを実装した型を受け取れる。
+/// rutie::VerifiedObjectの型には impl_from_ruby! で実装できる
+pub trait FromRuby: Sized {
+    /// argumentはエラーメッセージに使う引数の説明で、"argument 'name' of Foo#bar" のようにformatされる
+    fn from_ruby(from: &AnyObject, argument: &ArgumentName) -> Result<Self, AnyException>;
+
+    /// 引数が渡されなかったときの値。Noneのときは省略できない
+    fn from_missing() -> Option<Self> {
+        None
+    }
+}
+
+/// rutie::VerifiedObjectの型にFromRubyを実装する。=> の後はエラーメッセージに使うRubyのクラス名
+///
+/// ```ignore
+/// rutie::class!(Point);
+/// impl rutie::VerifiedObject for Point { ... }
+/// rutie_attr_backend::impl_from_ruby!(Point => "Point");
+/// ```
+#[macro_export]
+macro_rules! impl_from_ruby {
+    ($($struct_name:ty => $expected:expr),*) => ($(
+        impl $crate::FromRuby for $struct_name {
+            fn from_ruby(from: &rutie::AnyObject, argument: &$crate::ArgumentName) -> Result<Self, rutie::AnyException> {
+                rutie::Object::try_convert_to::<$struct_name>(from).map_err(|_| {
+                    let message = format!("{} for {}", $crate::no_implicit_conversion(from, $expected), argument);
+                    <rutie::AnyException as rutie::Exception>::new("TypeError", Some(&message))
+                })
+            }
+        }
+    )*)
+}
+
+impl_from_ruby!(
+    Array => "Array",
+    Boolean => "true or false",
+    Fixnum => "Integer",
+    Float => "Float",
+    Hash => "Hash",
+    Integer => "Integer",
+    NilClass => "nil",
+    RString => "String",
+    Symbol => "Symbol"
+);
+
+impl FromRuby for AnyObject {
+    fn from_ruby(from: &AnyObject, _argument: &ArgumentName) -> Result<Self, AnyException> {
+        Ok(from.clone())
+    }
+}
+
+impl FromRuby for String {
+    fn from_ruby(from: &AnyObject, argument: &ArgumentName) -> Result<Self, AnyException> {
+        RString::from_ruby(from, argument).map(|s| s.to_string())
+    }
+}
+
+impl FromRuby for bool {
+    fn from_ruby(from: &AnyObject, argument: &ArgumentName) -> Result<Self, AnyException> {
+        Boolean::from_ruby(from, argument).map(|b| b.to_bool())
+    }
+}
+
+// RubyのFloatを受け取るメソッドと同じく、Integerも受け取る
+impl FromRuby for f64 {
+    fn from_ruby(from: &AnyObject, argument: &ArgumentName) -> Result<Self, AnyException> {
+        if let Ok(integer) = from.try_convert_to::<Integer>() {
+            let f = integer.protect_send("to_f", &[])?;
+            return Float::from_ruby(&f, argument).map(|f| f.to_f64());
+        }
+        Float::from_ruby(from, argument).map(|f| f.to_f64())
+    }
+}
+
+impl FromRuby for f32 {
+    fn from_ruby(from: &AnyObject, argument: &ArgumentName) -> Result<Self, AnyException> {
+        f64::from_ruby(from, argument).map(|f| f as f32)
+    }
+}
+
+// Bignumがi64に収まらないときにrb_num2longがraiseするので、rb_protectの中で変換する
+fn integer_to_i64(integer: &Integer, argument: &ArgumentName) -> Result<i64, AnyException> {
+    let mut n = 0;
+    VM::protect(|| {
+        n = integer.to_i64();
+        NilClass::new().to_any_object()
+    })
+    .map_err(|_| {
+        let message = VM::error_pop().map(|e| e.message()).unwrap_or_default();
+        let message = format!("{} for {}", message, argument);
+        AnyException::new("RangeError", Some(&message))
+    })?;
+    Ok(n)
+}
+
+// 符号なしの型はrb_num2ullで変換する
+// rb_num2ullは負の数を2の補数にしてしまうので、負の数は先にRangeErrorにする
+fn integer_to_u64(integer: &Integer, type_name: &str, argument: &ArgumentName) -> Result<u64, AnyException> {
+    let negative = integer.protect_send("negative?", &[])?;
+    if negative.try_convert_to::<Boolean>().map(|b| b.to_bool()).unwrap_or(false) {
+        let n = integer.protect_send("to_s", &[])?.try_convert_to::<RString>().map(|s| s.to_string()).unwrap_or_default();
+        return Err(out_of_range(n, "small", type_name, argument));
+    }
+    let mut n = 0;
+    VM::protect(|| {
+        n = integer.to_u64();
+        NilClass::new().to_any_object()
+    })
+    .map_err(|_| {
+        let message = VM::error_pop().map(|e| e.message()).unwrap_or_default();
+        let message = format!("{} for {}", message, argument);
+        AnyException::new("RangeError", Some(&message))
+    })?;
+    Ok(n)
+}
+
+fn out_of_range(n: impl fmt::Display, size: &str, type_name: &str, argument: &ArgumentName) -> AnyException {
+    let message = format!("integer {} too {} to convert to `{}` for {}", n, size, type_name, argument);
+    AnyException::new("RangeError", Some(&message))
+}
+
+impl FromRuby for i64 {
+    fn from_ruby(from: &AnyObject, argument: &ArgumentName) -> Result<Self, AnyException> {
+        integer_to_i64(&Integer::from_ruby(from, argument)?, argument)
+    }
+}
+
+impl FromRuby for u64 {
+    fn from_ruby(from: &AnyObject, argument: &ArgumentName) -> Result<Self, AnyException> {
+        integer_to_u64(&Integer::from_ruby(from, argument)?, "u64", argument)
+    }
+}
+
+// i64やu64から変換できない値はRubyと同じくRangeErrorにする
+macro_rules! impl_from_ruby_for_signed_integer {
+    ($($t:ty),*) => ($(
+        impl FromRuby for $t {
+            fn from_ruby(from: &AnyObject, argument: &ArgumentName) -> Result<Self, AnyException> {
+                let n = i64::from_ruby(from, argument)?;
+                <$t>::try_from(n).map_err(|_| out_of_range(n, if n < 0 { "small" } else { "big" }, stringify!($t), argument))
+            }
+        }
+    )*)
+}
+
+macro_rules! impl_from_ruby_for_unsigned_integer {
+    ($($t:ty),*) => ($(
+        impl FromRuby for $t {
+            fn from_ruby(from: &AnyObject, argument: &ArgumentName) -> Result<Self, AnyException> {
+                let n = integer_to_u64(&Integer::from_ruby(from, argument)?, stringify!($t), argument)?;
+                <$t>::try_from(n).map_err(|_| out_of_range(n, "big", stringify!($t), argument))
+            }
+        }
+    )*)
+}
+
+impl_from_ruby_for_signed_integer!(i8, i16, i32, isize);
+impl_from_ruby_for_unsigned_integer!(u8, u16, u32, usize);
+
+// ArrayやHashは要素ごとに変換する
+impl<T: FromRuby> FromRuby for Vec<T> {
+    fn from_ruby(from: &AnyObject, argument: &ArgumentName) -> Result<Self, AnyException> {
+        convert_vec(from, argument)
+    }
+}
+
+impl<K, T> FromRuby for HashMap<K, T>
+where
+    K: FromHashKey + Eq + std::hash::Hash + fmt::Debug,
+    T: FromRuby,
+{
+    fn from_ruby(from: &AnyObject, argument: &ArgumentName) -> Result<Self, AnyException> {
+        convert_hash_map(from, argument)
+    }
+}
+
+impl<K, T> FromRuby for BTreeMap<K, T>
+where
+    K: FromHashKey + Ord + fmt::Debug,
+    T: FromRuby,
+{
+    fn from_ruby(from: &AnyObject, argument: &ArgumentName) -> Result<Self, AnyException> {
+        convert_btree_map(from, argument)
+    }
+}
+
+// 引数が省略されたときとnilのときはNoneにする
+impl<T: FromRuby> FromRuby for Option<T> {
+    fn from_ruby(from: &AnyObject, argument: &ArgumentName) -> Result<Self, AnyException> {
+        if from.is_nil() {
+            Ok(None)
+        } else {
+            T::from_ruby(from, argument).map(Some)
+        }
+    }
+
+    fn from_missing() -> Option<Self> {
+        Some(None)
+    }
+}
@@ -0,0 +1,38 @@
+use rutie::rubysys::class::{rb_ivar_get, rb_ivar_set};
+use rutie::rubysys::symbol::rb_intern2;
+use rutie::types::{c_long, Id};
+use rutie::{AnyObject, Object};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// rbclassのフィールドのインスタンス変数
+///
+/// 生成したコードでstaticにして、IDは最初に読んだときに一度だけinternする。
+/// attr_accessorのreaderやwriterをsendせずに直接読み書きする
+pub struct InstanceVariable {
+    name: &'static str,
+    id: AtomicUsize,
+}
+
+impl InstanceVariable {
+    /// nameは "@foo" のように@を付けた名前
+    pub const fn new(name: &'static str) -> Self {
+        Self { name, id: AtomicUsize::new(0) }
+    }
+
+    fn id(&self) -> Id {
+        let mut id = self.id.load(Ordering::Relaxed);
+        if id == 0 {
+            id = unsafe { rb_intern2(self.name.as_ptr() as *const _, self.name.len() as c_long) };
+            self.id.store(id, Ordering::Relaxed);
+        }
+        id
+    }
+
+    pub fn get(&self, object: &impl Object) -> AnyObject {
+        AnyObject::from(unsafe { rb_ivar_get(object.value(), self.id()) })
+    }
+
+    pub fn set(&self, object: &impl Object, value: AnyObject) {
+        unsafe { rb_ivar_set(object.value(), self.id(), value.value()) };
+    }
+}
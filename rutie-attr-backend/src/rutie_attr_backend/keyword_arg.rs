@@ -1,40 +1,42 @@
-use rutie::{Boolean, Fixnum, Float, Hash, Integer, NilClass, RString, Symbol, AnyException, AnyObject, Exception, Object};
+use rutie::{AnyException, AnyObject, Exception};
+use super::argument_name::ArgumentName;
+use super::from_ruby::FromRuby;
+use super::keyword_name::KeywordName;
 
 pub struct KwArg<T> {
     pub result: Result<T, AnyException>,
 }
 
 pub trait FromArgWithKeyAndDefault<T>: Sized {
-    fn from_arg_with_key_and_default(from: Option<&AnyObject>, key: &str, default_value: Option<&AnyObject>) -> Self;
+    /// default_valueはキーワードが渡されていないときだけ呼ぶ。Noneは必須のキーワード引数
+    fn from_arg_with_key_and_default<F: FnOnce() -> Result<AnyObject, AnyException>>(from: Option<&AnyObject>, key: &KeywordName, method: &str, default_value: Option<F>) -> Self;
 }
 
-macro_rules! impl_from_arg_with_key_and_default {
-    ($($struct_name:ty),*) => ($(
-        impl FromArgWithKeyAndDefault<$struct_name> for KwArg<$struct_name> {
-            fn from_arg_with_key_and_default(from: Option<&AnyObject>, key: &str, default_value: Option<&AnyObject>) -> KwArg<$struct_name> {
-                let result = if let Some(o) = from {
-                    if let Ok(h) = o.try_convert_to::<Hash>() {
-                        h.at(&Symbol::new(key)).try_convert_to::<$struct_name>()
-                    } else {
-                        Err(AnyException::new("ArgumentError", Some("missing argument")))
-                    }
-                } else {
-                    if let Some(o) = default_value {
-                        o.try_convert_to::<$struct_name>()
-                    } else {
-                        Err(AnyException::new("ArgumentError", Some("missing argument")))
-                    }
-                };
-                KwArg { result }
-            }
-        }
+// キーワード引数の値を取り出す。キーワードが渡されていないときはデフォルト値を使う
+fn keyword_value<F: FnOnce() -> Result<AnyObject, AnyException>>(from: Option<&AnyObject>, key: &KeywordName, default_value: Option<F>) -> Option<Result<AnyObject, AnyException>> {
+    let given = from.and_then(|o| key.lookup(o));
+    given.map(Ok).or_else(|| default_value.map(|f| f()))
+}
+
+// 必須のキーワード引数が渡されていないときはRubyと同じArgumentErrorにする
+fn missing_keyword(key: &KeywordName) -> AnyException {
+    let message = format!("missing keyword: :{}", key.name());
+    AnyException::new("ArgumentError", Some(&message))
+}
 
-        impl Into<$struct_name> for KwArg<$struct_name> {
-            fn into(self) -> $struct_name {
-                self.result.as_ref().ok().unwrap().value().clone().into()
-            }
-        }
-    )*)
+impl<T: FromRuby> FromArgWithKeyAndDefault<T> for KwArg<T> {
+    // Option<T>はキーワードが無いときもNoneにする
+    fn from_arg_with_key_and_default<F: FnOnce() -> Result<AnyObject, AnyException>>(from: Option<&AnyObject>, key: &KeywordName, method: &str, default_value: Option<F>) -> KwArg<T> {
+        let result = match keyword_value(from, key, default_value) {
+            Some(o) => o.and_then(|o| T::from_ruby(&o, &ArgumentName::keyword(key.name(), method))),
+            None => T::from_missing().ok_or_else(|| missing_keyword(key)),
+        };
+        KwArg { result }
+    }
 }
 
-impl_from_arg_with_key_and_default!(Boolean, Fixnum, Float, Hash, Integer, NilClass, RString, Symbol);
+impl<T> KwArg<T> {
+    pub fn into_value(self) -> T {
+        self.result.ok().unwrap()
+    }
+}
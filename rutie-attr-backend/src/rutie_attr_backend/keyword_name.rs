@@ -0,0 +1,51 @@
+use rutie::rubysys::symbol::rb_id2sym;
+use rutie::types::Value;
+use rutie::{AnyObject, Object, Symbol};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use super::ffi::rb_hash_lookup2;
+use super::method_definition::intern;
+
+/// キーワード引数の名前
+///
+/// 生成したコードでstaticにして、Symbolは最初に使ったときに一度だけinternする。
+/// rb_internで作ったSymbolはGCされないので、VALUEをそのままキャッシュできる
+pub struct KeywordName {
+    name: &'static str,
+    symbol: AtomicUsize,
+}
+
+impl KeywordName {
+    pub const fn new(name: &'static str) -> Self {
+        Self { name, symbol: AtomicUsize::new(0) }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn symbol(&self) -> Symbol {
+        let mut value = self.symbol.load(Ordering::Relaxed);
+        if value == 0 {
+            value = unsafe { rb_id2sym(intern(self.name)) }.value;
+            self.symbol.store(value, Ordering::Relaxed);
+        }
+        Symbol::from(Value::from(value))
+    }
+
+    // キーワードのHashからこのキーの値を取り出す。キーが無いときはNone
+    pub fn lookup(&self, hash: &AnyObject) -> Option<AnyObject> {
+        // キーワードのHashは呼び出しごとに作られるので、自分自身を値に持つことはない。
+        // 自分自身をキーが無いときの番兵にする
+        let hash = hash.value();
+        let value = unsafe { rb_hash_lookup2(hash, self.symbol().value(), hash) };
+        if value.value == hash.value {
+            None
+        } else {
+            Some(AnyObject::from(value))
+        }
+    }
+
+    pub fn is(&self, key: &AnyObject) -> bool {
+        key.value().value == self.symbol().value().value
+    }
+}
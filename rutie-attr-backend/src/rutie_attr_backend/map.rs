@@ -0,0 +1,136 @@
+use rutie::{Hash, RString, Symbol, AnyException, AnyObject, Exception, Object};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use super::argument_name::ArgumentName;
+use super::element::no_implicit_conversion;
+use super::from_ruby::FromRuby;
+
+/// Symbolのキーと文字列のキーを区別したいときに使うHashのキー
+///
+/// `{ a: 1, "a" => 2 }` は `HashKey::Symbol("a")` と `HashKey::String("a")` の2つのキーになる
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HashKey {
+    Symbol(String),
+    String(String),
+}
+
+impl HashKey {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Symbol(s) | Self::String(s) => s,
+        }
+    }
+}
+
+impl fmt::Display for HashKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Symbol(s) => write!(f, ":{}", s),
+            Self::String(s) => write!(f, "{:?}", s),
+        }
+    }
+}
+
+/// RubyのHashのキーをRustの型に変換する
+///
+/// `String` はSymbolのキーも文字列に正規化して受け付ける。
+/// 正規化した結果キーが重複したとき(`{ a: 1, "a" => 2 }`)はArgumentErrorになる。
+/// `HashKey` はSymbolと文字列を区別するので重複しない。
+pub trait FromHashKey: Sized {
+    fn from_hash_key(from: &AnyObject) -> Result<Self, String>;
+}
+
+impl FromHashKey for String {
+    fn from_hash_key(from: &AnyObject) -> Result<Self, String> {
+        HashKey::from_hash_key(from).map(|key| key.as_str().to_string())
+    }
+}
+
+impl FromHashKey for HashKey {
+    fn from_hash_key(from: &AnyObject) -> Result<Self, String> {
+        if let Ok(s) = from.try_convert_to::<Symbol>() {
+            Ok(Self::Symbol(s.to_string()))
+        } else if let Ok(s) = from.try_convert_to::<RString>() {
+            Ok(Self::String(s.to_string()))
+        } else {
+            Err(no_implicit_conversion(from, "String or Symbol"))
+        }
+    }
+}
+
+// Hashのキーと値をそれぞれ変換する。変換できなかったときは引数とキーをメッセージに含める
+fn convert_entries<K, T>(from: &AnyObject, argument: &ArgumentName) -> Result<Vec<(K, T)>, AnyException>
+where
+    K: FromHashKey,
+    T: FromRuby,
+{
+    let hash = match from.try_convert_to::<Hash>() {
+        Ok(hash) => hash,
+        Err(_) => {
+            let message = format!("{} for {}", no_implicit_conversion(from, "Hash"), argument);
+            return Err(AnyException::new("TypeError", Some(&message)));
+        },
+    };
+
+    let mut entries = Vec::with_capacity(hash.length());
+    let mut error = None;
+    hash.each(|key, value| {
+        if error.is_some() {
+            return;
+        }
+        let entry = K::from_hash_key(&key)
+            .map_err(|message| {
+                let message = format!("{} for {}", message, argument.key(&key));
+                AnyException::new("TypeError", Some(&message))
+            })
+            .and_then(|k| T::from_ruby(&value, &argument.value(&key)).map(|v| (k, v)));
+        match entry {
+            Ok(entry) => entries.push(entry),
+            Err(e) => error = Some(e),
+        }
+    });
+
+    match error {
+        Some(e) => Err(e),
+        None => Ok(entries),
+    }
+}
+
+fn duplicate_key_error(argument: &ArgumentName, key: &dyn fmt::Display) -> AnyException {
+    let message = format!("duplicate key {} after normalization in {}", key, argument);
+    AnyException::new("ArgumentError", Some(&message))
+}
+
+// AnyObjectをHashに変換してからHashMap<K, T>に変換する
+pub fn convert_hash_map<K, T>(from: &AnyObject, argument: &ArgumentName) -> Result<HashMap<K, T>, AnyException>
+where
+    K: FromHashKey + Eq + std::hash::Hash + fmt::Debug,
+    T: FromRuby,
+{
+    let entries = convert_entries::<K, T>(from, argument)?;
+    let mut map = HashMap::with_capacity(entries.len());
+    for (k, v) in entries {
+        if map.contains_key(&k) {
+            return Err(duplicate_key_error(argument, &format!("{:?}", k)));
+        }
+        map.insert(k, v);
+    }
+    Ok(map)
+}
+
+// AnyObjectをHashに変換してからBTreeMap<K, T>に変換する
+pub fn convert_btree_map<K, T>(from: &AnyObject, argument: &ArgumentName) -> Result<BTreeMap<K, T>, AnyException>
+where
+    K: FromHashKey + Ord + fmt::Debug,
+    T: FromRuby,
+{
+    let entries = convert_entries::<K, T>(from, argument)?;
+    let mut map = BTreeMap::new();
+    for (k, v) in entries {
+        if map.contains_key(&k) {
+            return Err(duplicate_key_error(argument, &format!("{:?}", k)));
+        }
+        map.insert(k, v);
+    }
+    Ok(map)
+}
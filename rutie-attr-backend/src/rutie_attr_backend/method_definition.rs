@@ -0,0 +1,36 @@
+use rutie::rubysys::class::rb_singleton_class;
+use rutie::rubysys::symbol::rb_id2sym;
+use rutie::types::{c_int, c_long, CallbackPtr, Id};
+use rutie::{AnyObject, Object};
+use super::ffi::{rb_define_method_id, rb_intern3, rb_utf8_encoding};
+
+/// 名前をUTF-8でinternする
+///
+/// rb_internはUS-ASCIIでinternするので、"größe" のような名前が正しいSymbolにならない
+pub fn intern(name: &str) -> Id {
+    unsafe { rb_intern3(name.as_ptr() as *const _, name.len() as c_long, rb_utf8_encoding()) }
+}
+
+/// extern fnをインスタンスメソッドとして登録する。arityが-1のときは argc, argv で呼ばれる
+///
+/// # Safety
+///
+/// `callback` はarityに合った引数を受け取る `extern fn` であること
+pub unsafe fn define_method(klass: &impl Object, name: &str, callback: CallbackPtr, arity: c_int) {
+    rb_define_method_id(klass.value(), intern(name), callback, arity);
+}
+
+/// extern fnを特異メソッドとして登録する
+///
+/// # Safety
+///
+/// `callback` はarityに合った引数を受け取る `extern fn` であること
+pub unsafe fn define_singleton_method(klass: &impl Object, name: &str, callback: CallbackPtr, arity: c_int) {
+    rb_define_method_id(rb_singleton_class(klass.value()), intern(name), callback, arity);
+}
+
+/// 登録したメソッドを private / protected にする
+pub fn set_visibility(klass: &impl Object, visibility: &str, name: &str) {
+    let symbol = AnyObject::from(unsafe { rb_id2sym(intern(name)) });
+    unsafe { klass.send(visibility, &[symbol]) };
+}
@@ -0,0 +1,19 @@
+use rutie::{AnyObject, Class, Object};
+
+/// メソッドの戻り値をRubyのオブジェクトに変換する
+///
+/// rutieのオブジェクトはそのまま返す。rbclassの構造体にはRubyのインスタンスを作る実装を生成する
+pub trait IntoRuby {
+    fn into_ruby(self) -> AnyObject;
+}
+
+impl<T: Object> IntoRuby for T {
+    fn into_ruby(self) -> AnyObject {
+        self.to_any_object()
+    }
+}
+
+/// initializeを呼ばずにRubyのクラスのインスタンスを作る
+pub fn allocate(class_name: &str) -> AnyObject {
+    unsafe { Class::from_existing(class_name).send("allocate", &[]) }
+}
@@ -0,0 +1,32 @@
+use rutie::{Array, AnyException, AnyObject};
+use super::argument_name::ArgumentName;
+use super::element::convert_elements;
+use super::from_ruby::FromRuby;
+
+pub struct SplatArg<T> {
+    pub result: Result<T, AnyException>,
+}
+
+pub trait FromSplatArg<T>: Sized {
+    fn from_splat_arg(from: &[AnyObject], name: &str, method: &str) -> Self;
+}
+
+impl FromSplatArg<Array> for SplatArg<Array> {
+    fn from_splat_arg(from: &[AnyObject], _name: &str, _method: &str) -> SplatArg<Array> {
+        let result = Ok(from.iter().cloned().collect());
+        SplatArg { result }
+    }
+}
+
+impl<T: FromRuby> FromSplatArg<Vec<T>> for SplatArg<Vec<T>> {
+    fn from_splat_arg(from: &[AnyObject], name: &str, method: &str) -> SplatArg<Vec<T>> {
+        let result = convert_elements(from.iter().cloned(), &ArgumentName::new(name, method));
+        SplatArg { result }
+    }
+}
+
+impl<T> SplatArg<T> {
+    pub fn into_value(self) -> T {
+        self.result.ok().unwrap()
+    }
+}
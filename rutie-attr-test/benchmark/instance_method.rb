@@ -0,0 +1,34 @@
+# frozen_string_literal: true
+
+# インスタンスメソッドの呼び出しのベンチマーク
+#
+#   cargo build --release && bundle exec ruby benchmark/instance_method.rb
+#
+# 変更前と比べるときは、変更前のコミットをcheckoutしてビルドし直してから同じスクリプトを実行する
+#
+#   git checkout <変更前のコミット> && cargo build --release && bundle exec ruby benchmark/instance_method.rb
+#
+# 変更前は呼び出しのたびにすべてのフィールドをreaderで読んで変換していた。
+# 今はメソッドが self.field で読むフィールドだけを変換し、そのフィールドだけを持つ構造体で呼ぶので、
+# フィールドを読まないメソッドほど差が出る
+$LOAD_PATH.unshift File.expand_path("../lib", __dir__)
+require "benchmark"
+require "rutie_foo"
+
+N = Integer(ENV.fetch("N", 1_000_000))
+
+foo = Foo.new
+foo.foo1 = "foo"
+foo.foo2 = 1
+
+Benchmark.bm(32) do |x|
+  x.report("instance method (no fields)") { N.times { foo._hoge!("b") } }
+  # 変更前のコミットには無いメソッドは飛ばす
+  if foo.respond_to?(:call)
+    x.report("instance method (one field)") { N.times { foo.call } }
+  end
+  if foo.respond_to?(:pair)
+    x.report("instance method (whole self)") { N.times { foo.pair } }
+  end
+  x.report("static method") { N.times { Foo.test?("a") } }
+end
@@ -1,3 +1,7 @@
 require 'rutie'
 
 Rutie.new(:rutie_attr_test).init 'Init_Foo', __dir__
+
+class Foo
+  STEP = 5
+end
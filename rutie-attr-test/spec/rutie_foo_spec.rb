@@ -30,4 +30,424 @@ RSpec.describe Foo do
     foo.foo2 = 100
     expect(foo._hoge!).to eq "-112"
   end
+
+  it "converts only the fields the instance method reads" do
+    foo = Foo.new
+    expect(foo._hoge!).to eq "-112"
+    expect { foo.call }.to raise_error(StandardError, "foo1 field is nil.")
+    foo.foo1 = "abc"
+    expect(foo.call).to eq "abc"
+  end
+
+  it "does not read the fields of another type that the instance method does not use" do
+    foo = Foo.new
+    foo.foo1 = "abc"
+    foo.foo2 = "not a number"
+    expect(foo.call).to eq "abc"
+    expect { foo + 1 }.to raise_error(TypeError)
+  end
+
+  it "converts every field when the method uses self as a whole" do
+    foo = Foo.new
+    foo.foo1 = "abc"
+    expect { foo.pair }.to raise_error(StandardError, "foo2 field is nil.")
+    foo.foo2 = 2
+    expect(foo.pair).to eq ["abc", 2]
+  end
+
+  it "sum? with array argument" do
+    expect(Foo.sum?([1, 2, 3])).to eq 6
+  end
+
+  it "sum? with empty array default" do
+    expect(Foo.sum?).to eq 0
+  end
+
+  it "sum? raises TypeError pointing at the failing element" do
+    expect { Foo.sum?([1, 2, 3, "4"]) }.to raise_error(TypeError, "no implicit conversion of String into Integer for element 3 of argument 'ids' of Foo.sum?")
+  end
+
+  it "join! with array default" do
+    expect(Foo.join!).to eq "a,b"
+    expect(Foo.join!(["x", "y", "z"])).to eq "x,y,z"
+  end
+
+  it "size? with Array argument" do
+    expect(Foo.size?([1, "a", nil])).to eq 3
+  end
+
+  it "total? with hash argument normalizes symbol and string keys" do
+    expect(Foo.total?({ a: 1, "b" => 2 })).to eq 3
+  end
+
+  it "total? with empty hash default" do
+    expect(Foo.total?).to eq 0
+  end
+
+  it "total? raises ArgumentError for keys duplicated after normalization" do
+    expect { Foo.total?({ a: 1, "a" => 2 }) }.to raise_error(ArgumentError, "duplicate key \"a\" after normalization in argument 'opts' of Foo.total?")
+  end
+
+  it "total? raises TypeError pointing at the failing value" do
+    expect { Foo.total?({ a: "1" }) }.to raise_error(TypeError, "no implicit conversion of String into Integer for value at key :a of argument 'opts' of Foo.total?")
+  end
+
+  it "keys! keeps symbol and string keys apart" do
+    expect(Foo.keys!).to eq [":a", ":c", "\"b\""]
+    expect(Foo.keys!({ a: 1, "a" => 2 })).to eq [":a", "\"a\""]
+  end
+
+  it "greet? treats an omitted Option argument as None" do
+    expect(Foo.greet?).to eq "hello nobody"
+  end
+
+  it "greet? treats nil as None" do
+    expect(Foo.greet?(nil)).to eq "hello nobody"
+    expect(Foo.greet?("ruby")).to eq "hello ruby"
+  end
+
+  it "maybe_pair does not let an Option argument before a required one be omitted" do
+    expect(Foo.maybe_pair(nil, 2)).to eq [nil, 2]
+    expect(Foo.maybe_pair(1, 2)).to eq [1, 2]
+    expect { Foo.maybe_pair(2) }.to raise_error(ArgumentError, "wrong number of arguments (given 1, expected 2)")
+  end
+
+  it "tag! treats an omitted or nil Option keyword as None" do
+    expect(Foo.tag!).to eq "x:none"
+    expect(Foo.tag!("y", tag: nil)).to eq "y:none"
+    expect(Foo.tag!("y", tag: :t)).to eq "y:t"
+  end
+
+  it "point! with required and optional keywords" do
+    expect(Foo.point!(x: 1)).to eq "(1, 0)"
+    expect(Foo.point!(x: 1, y: 2)).to eq "(1, 2)"
+  end
+
+  it "point! raises ArgumentError when a required keyword is missing" do
+    expect { Foo.point! }.to raise_error(ArgumentError, "missing keyword: :x")
+    expect { Foo.point!(y: 2) }.to raise_error(ArgumentError, "missing keyword: :x")
+  end
+
+  it "add! collects remaining positional arguments into a Vec" do
+    expect(Foo.add!(1)).to eq 1
+    expect(Foo.add!(1, 2, 3)).to eq 6
+  end
+
+  it "add! raises TypeError pointing at the failing splat element" do
+    expect { Foo.add!(1, 2, "3") }.to raise_error(TypeError, "no implicit conversion of String into Integer for element 1 of argument 'rest' of Foo.add!")
+  end
+
+  it "wrap? fills required, optional, splat and post arguments like Ruby" do
+    expect(Foo.wrap?("1", "2")).to eq ["1", "b", [], "2"]
+    expect(Foo.wrap?("1", "2", "3")).to eq ["1", "2", [], "3"]
+    expect(Foo.wrap?("1", "2", "3", "4", "5")).to eq ["1", "2", ["3", "4"], "5"]
+  end
+
+  it "paint! collects leftover keywords into **opts" do
+    expect(Foo.paint!).to eq ["red", {}]
+    expect(Foo.paint!(color: "blue", size: 1, weight: 2)).to eq ["blue", { size: 1, weight: 2 }]
+  end
+
+  it "options? converts **opts into a BTreeMap" do
+    expect(Foo.options?(b: 1, a: 2)).to eq ["a", "b"]
+  end
+
+  it "point! raises ArgumentError for unknown keywords without **opts" do
+    expect { Foo.point!(x: 1, colour: 1) }.to raise_error(ArgumentError, "unknown keyword: :colour")
+    expect { Foo.point!(x: 1, colour: 1, z: 2) }.to raise_error(ArgumentError, "unknown keywords: :colour, :z")
+  end
+
+  it "twice! calls the given block" do
+    expect(Foo.twice! { |i| i * 10 }).to eq [0, 10]
+  end
+
+  it "twice! raises LocalJumpError without a block" do
+    expect { Foo.twice! }.to raise_error(LocalJumpError, "no block given (yield)")
+  end
+
+  it "block? accepts an optional block" do
+    expect(Foo.block?).to eq false
+    expect(Foo.block? {}).to eq true
+  end
+
+  it "map_sum! converts the result of a typed block" do
+    expect(Foo.map_sum!([1, 2, 3]) { |i| i * 2 }).to eq 12
+    expect { Foo.map_sum!([1]) { |i| i.to_s } }.to raise_error(TypeError, "no implicit conversion of String into Integer for block result")
+  end
+
+  it "yield_twice! yields to the block without declaring it" do
+    expect(Foo.yield_twice! { |i| i * 3 }).to eq 9
+  end
+
+  it "raises exceptions raised inside the block after returning Err" do
+    expect { Foo.map_sum!([1]) { raise "boom" } }.to raise_error(RuntimeError, "boom")
+    expect { Foo.yield_twice! { |i| raise "boom" if i == 2; i } }.to raise_error(RuntimeError, "boom")
+    expect { Foo.yield_twice! }.to raise_error(LocalJumpError, "no block given (yield)")
+  end
+
+  it "continues break and throw out of the block" do
+    expect(Foo.map_sum!([1, 2]) { |i| break i * 10 }).to eq 10
+    expect(Foo.yield_twice! { |i| break :stop if i == 2; i }).to eq :stop
+    expect(catch(:done) { Foo.yield_twice! { |i| throw :done, i * 7 } }).to eq 7
+    expect(Foo.yield_twice! { |i| next i * 10 }).to eq 30
+  end
+
+  it "divide raises the Err returned from the method" do
+    expect(Foo.divide(7, 2)).to eq 3
+    expect { Foo.divide(1, 0) }.to raise_error(ZeroDivisionError, "divided by 0")
+  end
+
+  it "full! accepts a full Ruby parameter list in the signature order" do
+    expect(Foo.full!("1", "2", d: 3)).to eq ["1", "b", [], "2", 3, 1, {}, false]
+    expect(Foo.full!("1", "2", "3", "4", "5", d: 3, e: 4, f: 5) {}).to eq ["1", "2", ["3", "4"], "5", 3, 4, { f: 5 }, true]
+  end
+
+  it "partial! takes parameters missing from the signature first in declaration order" do
+    expect(Foo.partial!("a")).to eq ["a", "b"]
+    expect(Foo.partial!("a", "c")).to eq ["a", "c"]
+    expect(Foo.method(:partial!).arity).to eq(-2)
+  end
+
+  it "raises ArgumentError with Ruby's message for a wrong number of arguments" do
+    expect { Foo.size? }.to raise_error(ArgumentError, "wrong number of arguments (given 0, expected 1)")
+    expect { Foo.test?("a", "b") }.to raise_error(ArgumentError, "wrong number of arguments (given 2, expected 0..1)")
+    expect { Foo.add! }.to raise_error(ArgumentError, "wrong number of arguments (given 0, expected 1+)")
+    expect { Foo.wrap?("1") }.to raise_error(ArgumentError, "wrong number of arguments (given 1, expected 2+)")
+  end
+
+  it "reports a wrong number of arguments before missing keywords" do
+    expect { Foo.full!("1") }.to raise_error(ArgumentError, "wrong number of arguments (given 1, expected 2+; required keyword: d)")
+    expect { Foo.full!("1", "2") }.to raise_error(ArgumentError, "missing keyword: :d")
+  end
+
+  it "registers methods without optional parameters with a fixed arity" do
+    expect(Foo.method(:size?).arity).to eq 1
+    expect(Foo.method(:twice!).arity).to eq 0
+    expect(Foo.method(:yield_twice!).arity).to eq 0
+    expect(Foo.method(:test?).arity).to eq(-1)
+  end
+
+  it "merge! keeps a positional Hash apart from keywords" do
+    expect(Foo.merge!({ prefix: "y" })).to eq [{ prefix: "y" }, "x"]
+    expect(Foo.merge!(prefix: "y")).to eq [{}, "y"]
+    expect(Foo.merge!({ a: 1 }, prefix: "y")).to eq [{ a: 1 }, "y"]
+    expect(Foo.merge!(**{})).to eq [{}, "x"]
+  end
+
+  it "does not treat a positional Hash as keywords" do
+    expect { Foo.point!({ x: 1 }) }.to raise_error(ArgumentError, "wrong number of arguments (given 1, expected 0; required keyword: x)")
+    expect { Foo.paint!({ color: "blue" }) }.to raise_error(ArgumentError, "wrong number of arguments (given 1, expected 0)")
+  end
+
+  it "passes keywords to a method without keyword parameters as a positional Hash" do
+    expect(Foo.size?(a: 1)).to eq 1
+  end
+
+  it "defaults? accepts floats, negatives, symbols, constants and Rust constants as defaults" do
+    expect(Foo.defaults?).to eq [1.5, -1, -0.5, :fast, Float::INFINITY, 100]
+    expect(Foo.defaults?(2.0, 3)).to eq [2.0, 3, -0.5, :fast, Float::INFINITY, 100]
+  end
+
+  it "limits accepts hex, underscored and typed integers up to the u64 range and symbols ending with ?" do
+    expect(Foo.limits).to eq [2**64 - 1, -2**63, 1000, :fast?]
+  end
+
+  it "step? looks up constant defaults in the class" do
+    expect(Foo.step?(m: 1)).to eq 6
+    expect { Foo.step? }.to raise_error(NameError, /uninitialized constant Foo::NO_SUCH_STEP/)
+  end
+
+  it "fresh! builds the default value only when the argument is omitted" do
+    expect(Foo.fresh!).to eq [1]
+    expect(Foo.fresh!).to eq [1]
+    list = [0]
+    expect(Foo.fresh!(list)).to equal list
+  end
+
+  it "secret? is a private instance method" do
+    foo = Foo.new
+    foo.foo1 = "foo"
+    foo.foo2 = 1
+    expect(Foo.private_instance_methods).to include :secret?
+    expect { foo.secret? }.to raise_error(NoMethodError)
+    expect(foo.send(:secret?)).to eq "secret"
+  end
+
+  it "peer? is a protected instance method" do
+    expect(Foo.protected_instance_methods).to include :peer?
+    expect { Foo.new.peer? }.to raise_error(NoMethodError)
+  end
+
+  it "helper! is a private singleton method" do
+    expect(Foo.private_methods).to include :helper!
+    expect { Foo.helper! }.to raise_error(NoMethodError)
+    expect(Foo.send(:helper!)).to eq 42
+  end
+
+  it "double? is a module function" do
+    expect(Foo.double?(2)).to eq 4
+    expect(Foo.private_instance_methods).to include :double?
+    expect(Foo.new.send(:double?, 3)).to eq 6
+  end
+
+  describe "operator and special method names" do
+    let(:foo) do
+      foo = Foo.new
+      foo.foo1 = "abc"
+      foo.foo2 = 2
+      foo
+    end
+
+    it "defines binary operators" do
+      expect(foo == 2).to eq true
+      expect(foo == 3).to eq false
+      expect(foo + 3).to eq 5
+      expect(foo <=> 3).to eq(-1)
+    end
+
+    it "defines unary operators" do
+      expect(-foo).to eq(-2)
+    end
+
+    it "defines [] and []=" do
+      expect(foo[1]).to eq "b"
+      expect(foo.public_send(:[]=, 0, "z")).to eq [0, "z"]
+    end
+
+    it "defines setters" do
+      expect(foo.public_send(:label=, "x")).to eq "x"
+      expect(Foo.instance_method(:label=).arity).to eq 1
+    end
+
+    it "defines plain names" do
+      expect(foo.to_s).to eq "abc"
+      expect(Foo.call(2, 3)).to eq 6
+      expect(Foo.call(2)).to eq 2
+    end
+
+    it "defines names given as string" do
+      expect(Foo.größe).to eq 10
+      expect(Foo.größe(2)).to eq 20
+      expect(Foo.grösse(3)).to eq 30
+      expect(Foo._internal?).to eq true
+    end
+
+    it "registers non-ASCII names as UTF-8 symbols" do
+      expect(Foo.singleton_methods).to include(:größe, :grösse)
+      expect(Foo.method(:größe).name.encoding).to eq Encoding::UTF_8
+      expect(Foo.private_instance_methods).to include(:prüfen?)
+      expect { Foo.new.prüfen? }.to raise_error(NoMethodError)
+      expect(Foo.new.send(:prüfen?)).to eq true
+    end
+  end
+
+  it "defines aliases for the same method" do
+    expect(Foo.length?([1, 2])).to eq 2
+    expect(Foo.count?([1, 2, 3])).to eq 3
+    expect(Foo.method(:length?).arity).to eq 1
+  end
+
+  it "defines the same name as an instance method and a singleton method" do
+    foo = Foo.new
+    foo.foo1 = "abc"
+    foo.foo2 = 1
+    expect(foo.call).to eq "abc"
+    expect(Foo.call(2, 3)).to eq 6
+  end
+
+  it "defines singleton methods with rbdefself and singleton option" do
+    expect(Foo.origin).to eq 0
+    expect(Foo.unit).to eq 1
+    expect(Foo.new).not_to respond_to(:origin)
+    expect(Foo.new).not_to respond_to(:unit)
+  end
+
+  it "returns a new Foo from a factory method" do
+    foo = Foo.parse("abc,3")
+    expect(foo).to be_a Foo
+    expect(foo.foo1).to eq "abc"
+    expect(foo.foo2).to eq 3
+  end
+
+  it "returns a new Foo from an instance method" do
+    foo = Foo.parse("abc,3").succ
+    expect(foo.foo1).to eq "abc"
+    expect(foo.foo2).to eq 4
+  end
+
+  it "receives Foo as an argument" do
+    foo = Foo.parse("abc,3")
+    expect(foo.same?(Foo.parse("xyz,3"))).to eq true
+    expect(foo.same?(Foo.parse("abc,4"))).to eq false
+    expect(foo.sum_with(Foo.parse("xyz,2"))).to eq 5
+    expect(foo.sum_with).to eq 3
+  end
+
+  it "raises TypeError when the argument is not Foo" do
+    foo = Foo.parse("abc,3")
+    expect { foo.same?(1) }.to raise_error(TypeError, "wrong argument type Integer (expected Foo) for argument 'other' of Foo#same?")
+  end
+
+  it "receives a type implementing FromRuby" do
+    expect(Foo.half(4)).to eq 2
+    expect(Foo.half(4, 2)).to eq 3
+    expect { Foo.half(3) }.to raise_error(ArgumentError, "argument 'n' of Foo.half must be even")
+  end
+
+  it "receives FromRuby types in Vec, *rest and Hash values" do
+    expect(Foo.halves([2, 4], 6, by: { "a" => 8 })).to eq 10
+    expect { Foo.halves([2, 3]) }.to raise_error(ArgumentError, "element 1 of argument 'list' of Foo.halves must be even")
+    expect { Foo.halves([], 1) }.to raise_error(ArgumentError, "element 0 of argument 'rest' of Foo.halves must be even")
+    expect { Foo.halves([], by: { a: 1 }) }.to raise_error(ArgumentError, "value at key :a of keyword 'by' of Foo.halves must be even")
+  end
+
+  it "receives Rust primitives as arguments" do
+    expect(Foo.describe("box", 3)).to eq "box x3 (1.5)"
+    expect(Foo.describe("box", 3, 2.0, true)).to eq "BOX X3 (2)"
+    expect { Foo.describe(:box, 3) }.to raise_error(TypeError, "no implicit conversion of Symbol into String for argument 'name' of Foo.describe")
+    expect { Foo.describe("box", 256) }.to raise_error(RangeError, "integer 256 too big to convert to `u8` for argument 'count' of Foo.describe")
+    expect { Foo.describe("box", -1) }.to raise_error(RangeError, "integer -1 too small to convert to `u8` for argument 'count' of Foo.describe")
+    expect { Foo.describe("box", 2**64) }.to raise_error(RangeError, /for argument 'count' of Foo.describe/)
+  end
+
+  it "converts Integer to f64 like Ruby methods taking Float" do
+    expect(Foo.describe("box", 3, 2)).to eq "box x3 (2)"
+  end
+
+  it "receives the whole u64 range and rejects negative integers" do
+    expect(Foo.id_of(2**64 - 1)).to eq "18446744073709551615"
+    expect(Foo.id_of(2**63)).to eq "9223372036854775808"
+    expect { Foo.id_of(-1) }.to raise_error(RangeError, "integer -1 too small to convert to `u64` for argument 'id' of Foo.id_of")
+    expect { Foo.id_of(-2**64) }.to raise_error(RangeError, "integer -18446744073709551616 too small to convert to `u64` for argument 'id' of Foo.id_of")
+    expect { Foo.id_of(2**64) }.to raise_error(RangeError, /for argument 'id' of Foo.id_of/)
+  end
+
+  describe "implicit conversion with Coerce" do
+    let(:text) { Class.new { def to_str; "abc"; end }.new }
+    let(:two) { Class.new { def to_int; 2; end }.new }
+    let(:list) { Class.new { def to_ary; [1, 2, 3]; end }.new }
+
+    it "calls to_str, to_int and to_ary" do
+      expect(Foo.shout("ab")).to eq "AB"
+      expect(Foo.shout(text)).to eq "ABC"
+      expect(Foo.shout("ab", two)).to eq "ABAB"
+      expect(Foo.coerced_sum(list)).to eq 6
+      expect(Foo.coerced_sum).to eq 0
+    end
+
+    it "raises TypeError when the conversion gives another type" do
+      bad = Class.new { def to_str; 1; end }.new
+      expect { Foo.shout(bad) }.to raise_error(TypeError, /#to_str gives Integer\)/)
+    end
+
+    it "raises TypeError when the object has no conversion method" do
+      expect { Foo.shout(1) }.to raise_error(TypeError, "no implicit conversion of Integer into String for argument 'text' of Foo.shout")
+    end
+  end
+
+  it "names the argument and the method in TypeError" do
+    expect { Foo.shout(nil) }.to raise_error(TypeError, "no implicit conversion of nil into String for argument 'text' of Foo.shout")
+    expect { Foo.point!(x: "1") }.to raise_error(TypeError, "no implicit conversion of String into Integer for keyword 'x' of Foo.point!")
+    expect { Foo.parse("a,1").same?(true) }.to raise_error(TypeError, "wrong argument type TrueClass (expected Foo) for argument 'other' of Foo#same?")
+  end
 end
@@ -1,14 +1,32 @@
-use rutie::{Fixnum, Object, RString};
-use rutie_attr::{rbclass, rbdef, rbmethods};
-use std::convert::TryFrom;
+use rutie::{AnyException, AnyObject, Array, Boolean, Fixnum, Float, Hash, Integer, Object, Proc, RString, Symbol};
+use rutie_attr::{rbclass, rbdef, rbdefself, rbmethods};
+use rutie_attr_backend::{yield_block, ArgumentName, Block, BlockError, Coerce, FromRuby, HashKey};
+use std::collections::{BTreeMap, HashMap};
 use rutie::Exception;
 
+const LIMIT: i64 = 100;
+
 #[rbclass]
 pub struct Foo {
     foo1: RString,
     foo2: Fixnum,
 }
 
+// FromRubyを実装すると独自の型も引数で受け取れる
+pub struct Even(i64);
+
+impl FromRuby for Even {
+    fn from_ruby(from: &AnyObject, argument: &ArgumentName) -> Result<Self, AnyException> {
+        let n = i64::from_ruby(from, argument)?;
+        if n % 2 == 0 {
+            Ok(Even(n))
+        } else {
+            let message = format!("{} must be even", argument);
+            Err(AnyException::new("ArgumentError", Some(&message)))
+        }
+    }
+}
+
 #[rbmethods]
 impl Foo {
     #[rbdef(test?(a = "a"))]
@@ -20,4 +38,355 @@ impl Foo {
     fn hoge(&self, b: RString) -> RString {
         b
     }
+
+    #[rbdef(sum?(ids = []))]
+    fn sum(ids: Vec<i64>) -> Fixnum {
+        Fixnum::new(ids.iter().sum())
+    }
+
+    #[rbdef(join!(words = ["a", "b"]))]
+    fn join(words: Vec<String>) -> RString {
+        RString::new_utf8(&words.join(","))
+    }
+
+    #[rbdef(size?(list), alias = "length?", alias = "count?")]
+    fn size(list: Array) -> Fixnum {
+        Fixnum::new(list.length() as i64)
+    }
+
+    #[rbdef(total?(opts = {}))]
+    fn total(opts: HashMap<String, i64>) -> Fixnum {
+        Fixnum::new(opts.values().sum())
+    }
+
+    #[rbdef(keys!(opts = { a: 1, "b" => 2, "c": 3 }))]
+    fn keys(opts: BTreeMap<HashKey, i64>) -> Array {
+        opts.keys()
+            .map(|k| RString::new_utf8(&k.to_string()).to_any_object())
+            .collect()
+    }
+
+    #[rbdef(greet?)]
+    fn greet(name: Option<RString>) -> RString {
+        let name = name.map(|n| n.to_string()).unwrap_or_else(|| "nobody".to_string());
+        RString::new_utf8(&format!("hello {}", name))
+    }
+
+    #[rbdef(maybe_pair(a, b))]
+    fn maybe_pair(a: Option<Fixnum>, b: Fixnum) -> Array {
+        let mut result = Array::new();
+        result.push(a.map(|a| a.to_any_object()).unwrap_or_else(|| rutie::NilClass::new().to_any_object()));
+        result.push(b);
+        result
+    }
+
+    #[rbdef(tag!(a = "x", tag: nil))]
+    fn tag(a: RString, tag: Option<Symbol>) -> RString {
+        let tag = tag.map(|t| t.to_string()).unwrap_or_else(|| "none".to_string());
+        RString::new_utf8(&format!("{}:{}", a.to_str(), tag))
+    }
+
+    #[rbdef(point!(x:, y: 0))]
+    fn point(x: Fixnum, y: Fixnum) -> RString {
+        RString::new_utf8(&format!("({}, {})", x.to_i64(), y.to_i64()))
+    }
+
+    #[rbdef(add!(first, *rest))]
+    fn add(first: Fixnum, rest: Vec<i64>) -> Fixnum {
+        Fixnum::new(first.to_i64() + rest.iter().sum::<i64>())
+    }
+
+    #[rbdef(wrap?(a, b = "b", *rest, c))]
+    fn wrap(a: RString, b: RString, rest: Array, c: RString) -> Array {
+        let mut result = Array::new();
+        result.push(a);
+        result.push(b);
+        result.push(rest);
+        result.push(c);
+        result
+    }
+
+    #[rbdef(paint!(color: "red", **opts))]
+    fn paint(color: RString, opts: Hash) -> Array {
+        let mut result = Array::new();
+        result.push(color);
+        result.push(opts);
+        result
+    }
+
+    #[rbdef(twice!(&blk))]
+    fn twice(blk: Proc) -> Array {
+        (0..2)
+            .map(|i| blk.call(&[Fixnum::new(i).to_any_object()]))
+            .collect()
+    }
+
+    #[rbdef(block?(&blk))]
+    fn block(blk: Option<Proc>) -> Boolean {
+        Boolean::new(blk.is_some())
+    }
+
+    #[rbdef(map_sum!(ids, &blk))]
+    fn map_sum(ids: Vec<i64>, blk: Block<(Fixnum,), i64>) -> Result<Fixnum, BlockError> {
+        let mut sum = 0;
+        for id in ids {
+            sum += blk.call((Fixnum::new(id),))?;
+        }
+        Ok(Fixnum::new(sum))
+    }
+
+    #[rbdef(yield_twice!)]
+    fn yield_twice() -> Result<Fixnum, BlockError> {
+        let first: i64 = yield_block((Fixnum::new(1),))?;
+        let second: i64 = yield_block((Fixnum::new(2),))?;
+        Ok(Fixnum::new(first + second))
+    }
+
+    #[rbdef(divide(a, b))]
+    fn divide(a: i64, b: i64) -> Result<Fixnum, AnyException> {
+        match a.checked_div(b) {
+            Some(n) => Ok(Fixnum::new(n)),
+            None => Err(AnyException::new("ZeroDivisionError", Some("divided by 0"))),
+        }
+    }
+
+    #[rbdef(options?(**opts))]
+    fn options(opts: BTreeMap<String, i64>) -> Array {
+        opts.keys()
+            .map(|k| RString::new_utf8(k).to_any_object())
+            .collect()
+    }
+
+    #[rbdef(def full!(a, b = "b", *rest, c, d:, e: 1, **opts, &blk))]
+    fn full(blk: Option<Proc>, opts: Hash, a: RString, b: RString, c: RString, rest: Array, d: Fixnum, e: Fixnum) -> Array {
+        let mut result = Array::new();
+        result.push(a);
+        result.push(b);
+        result.push(rest);
+        result.push(c);
+        result.push(d);
+        result.push(e);
+        result.push(opts);
+        result.push(Boolean::new(blk.is_some()));
+        result
+    }
+
+    #[rbdef(partial!(b = "b"))]
+    fn partial(a: RString, b: RString) -> Array {
+        let mut result = Array::new();
+        result.push(a);
+        result.push(b);
+        result
+    }
+
+    #[rbdef(merge!(base = {}, prefix: "x"))]
+    fn merge(base: Hash, prefix: RString) -> Array {
+        let mut result = Array::new();
+        result.push(base);
+        result.push(prefix);
+        result
+    }
+
+    #[rbdef(defaults?(ratio = 1.5, offset = -1, scale = -0.5, mode = :fast, max = Float::INFINITY, limit = rust!(LIMIT)))]
+    fn defaults(ratio: Float, offset: Integer, scale: Float, mode: Symbol, max: Float, limit: Integer) -> Array {
+        let mut result = Array::new();
+        result.push(ratio);
+        result.push(offset);
+        result.push(scale);
+        result.push(mode);
+        result.push(max);
+        result.push(limit);
+        result
+    }
+
+    #[rbdef(limits(max = 0xFFFF_FFFF_FFFF_FFFF, min = -0x8000_0000_0000_0000, typed = 1_000_i64, mode = :fast?))]
+    fn limits(max: Integer, min: Integer, typed: Integer, mode: Symbol) -> Array {
+        let mut result = Array::new();
+        result.push(max);
+        result.push(min);
+        result.push(typed);
+        result.push(mode);
+        result
+    }
+
+    #[rbdef(step?(n: STEP, m: NO_SUCH_STEP))]
+    fn step(n: Integer, m: Integer) -> Fixnum {
+        Fixnum::new(n.to_i64() + m.to_i64())
+    }
+
+    #[rbdef(fresh!(list = []))]
+    fn fresh(mut list: Array) -> Array {
+        list.push(Fixnum::new(1));
+        list
+    }
+
+    #[rbdef(secret?, visibility = private)]
+    fn secret(&self) -> RString {
+        RString::new_utf8("secret")
+    }
+
+    #[rbdef(peer?, visibility = protected)]
+    fn peer(&self) -> RString {
+        RString::new_utf8("peer")
+    }
+
+    #[rbdef(helper!, visibility = private)]
+    fn helper() -> Fixnum {
+        Fixnum::new(42)
+    }
+
+    #[rbdef(double?(n), visibility = module_function)]
+    fn double(n: Fixnum) -> Fixnum {
+        Fixnum::new(n.to_i64() * 2)
+    }
+
+    #[rbdef(==(other))]
+    fn equal(&self, other: Fixnum) -> Boolean {
+        Boolean::new(self.foo2.to_i64() == other.to_i64())
+    }
+
+    #[rbdef(+(other))]
+    fn plus(&self, other: Fixnum) -> Fixnum {
+        Fixnum::new(self.foo2.to_i64() + other.to_i64())
+    }
+
+    #[rbdef(<=>(other))]
+    fn compare(&self, other: Fixnum) -> Fixnum {
+        Fixnum::new(self.foo2.to_i64().cmp(&other.to_i64()) as i64)
+    }
+
+    #[rbdef(-@)]
+    fn negate(&self) -> Fixnum {
+        Fixnum::new(-self.foo2.to_i64())
+    }
+
+    #[rbdef([](index))]
+    fn index(&self, index: Fixnum) -> RString {
+        let c = self.foo1.to_str().chars().nth(index.to_i64() as usize).unwrap_or_default();
+        RString::new_utf8(&c.to_string())
+    }
+
+    #[rbdef([]=(index, value))]
+    fn index_set(&self, index: Fixnum, value: RString) -> Array {
+        let mut result = Array::new();
+        result.push(index);
+        result.push(value);
+        result
+    }
+
+    #[rbdef(label=(value))]
+    fn set_label(&self, value: RString) -> RString {
+        value
+    }
+
+    #[rbdef(to_s)]
+    fn to_s(&self) -> RString {
+        RString::new_utf8(self.foo1.to_str())
+    }
+
+    #[rbdef(call(a, b = 1))]
+    fn call(a: Fixnum, b: Fixnum) -> Fixnum {
+        Fixnum::new(a.to_i64() * b.to_i64())
+    }
+
+    // 特異メソッドの call とは別のメソッド
+    #[rbdef(call)]
+    fn instance_call(&self) -> RString {
+        RString::new_utf8(self.foo1.to_str())
+    }
+
+    #[rbdef(name = "größe", args(a = 1), alias = "grösse")]
+    fn groesse(a: Fixnum) -> Fixnum {
+        Fixnum::new(a.to_i64() * 10)
+    }
+
+    #[rbdef(name = "prüfen?", visibility = private)]
+    fn pruefen(&self) -> Boolean {
+        Boolean::new(true)
+    }
+
+    #[rbdef(name = "_internal?")]
+    fn internal() -> Boolean {
+        Boolean::new(true)
+    }
+
+    #[rbdefself(origin)]
+    fn origin() -> Fixnum {
+        Fixnum::new(0)
+    }
+
+    #[rbdef(unit, singleton)]
+    fn unit() -> Fixnum {
+        Fixnum::new(1)
+    }
+
+    #[rbdef(parse(s))]
+    fn parse(s: RString) -> Foo {
+        let (label, number) = s.to_str().split_once(',').unwrap_or((s.to_str(), "0"));
+        Foo {
+            foo1: RString::new_utf8(label),
+            foo2: Fixnum::new(number.parse().unwrap_or_default()),
+        }
+    }
+
+    #[rbdef(succ)]
+    fn succ(&self) -> Foo {
+        Foo {
+            foo1: RString::new_utf8(self.foo1.to_str()),
+            foo2: Fixnum::new(self.foo2.to_i64() + 1),
+        }
+    }
+
+    // self をまるごと使うときはすべてのフィールドを変換する
+    #[rbdef(pair)]
+    fn pair(&self) -> Array {
+        let Foo { foo1, foo2 } = self;
+        let mut result = Array::new();
+        result.push(RString::new_utf8(foo1.to_str()));
+        result.push(Fixnum::new(foo2.to_i64()));
+        result
+    }
+
+    #[rbdef(same?(other))]
+    fn same(&self, other: Foo) -> Boolean {
+        Boolean::new(self.foo2.to_i64() == other.foo2.to_i64())
+    }
+
+    #[rbdef(sum_with(other = nil))]
+    fn sum_with(&self, other: Option<Foo>) -> Fixnum {
+        let other = other.map(|other| other.foo2.to_i64()).unwrap_or_default();
+        Fixnum::new(self.foo2.to_i64() + other)
+    }
+
+    #[rbdef(half(n, m = nil))]
+    fn half(n: Even, m: Option<Even>) -> Fixnum {
+        Fixnum::new(n.0 / 2 + m.map_or(0, |m| m.0 / 2))
+    }
+
+    #[rbdef(halves(list, *rest, by: {}))]
+    fn halves(list: Vec<Even>, rest: Vec<Even>, by: BTreeMap<String, Even>) -> Fixnum {
+        let sum = list.iter().chain(rest.iter()).chain(by.values()).map(|n| n.0 / 2).sum();
+        Fixnum::new(sum)
+    }
+
+    #[rbdef(describe(name, count, ratio = 1.5, loud = false))]
+    fn describe(name: String, count: u8, ratio: f64, loud: bool) -> RString {
+        let text = format!("{} x{} ({})", name, count, ratio);
+        RString::new_utf8(&if loud { text.to_uppercase() } else { text })
+    }
+
+    #[rbdef(id_of(id))]
+    fn id_of(id: u64) -> RString {
+        RString::new_utf8(&id.to_string())
+    }
+
+    #[rbdef(shout(text, times = 1))]
+    fn shout(text: Coerce<RString>, times: Coerce<Integer>) -> RString {
+        RString::new_utf8(&text.to_str().to_uppercase().repeat(times.to_i64() as usize))
+    }
+
+    #[rbdef(coerced_sum(list = []))]
+    fn coerced_sum(list: Coerce<Vec<i64>>) -> Fixnum {
+        Fixnum::new(list.into_inner().iter().sum())
+    }
 }
@@ -1,3 +1,5 @@
+use std::iter::FromIterator;
+
 #[derive(Debug)]
 pub struct Argument {
     pub name: syn::PatIdent,
@@ -8,8 +10,43 @@ pub struct Argument {
 
 impl Argument {
     pub fn validate(&self) -> syn::Result<()> {
+        if let Some(default_value) = &self.default_value {
+            default_value.validate()?;
+        }
         Ok(())
     }
+
+    // デフォルト値がRustの引数の型に変換できないときはErr
+    // マクロでは判定できない型のデフォルト値は、呼び出したときにFromRubyで変換する
+    pub fn check_default_value(&self) -> Result<(), String> {
+        match &self.default_value {
+            Some(default_value) => default_value.check_type(&self.ty),
+            None => Ok(()),
+        }
+    }
+
+    // rust!(expr) のデフォルト値は、式が引数の型に変換できるかをコンパイル時にチェックする
+    pub fn default_value_assertions(&self) -> Vec<proc_macro2::TokenStream> {
+        match &self.default_value {
+            Some(default_value) => default_value.type_assertions(&self.ty),
+            None => Vec::new(),
+        }
+    }
+
+    // 呼び出し側で省略できる引数
+    pub fn is_optional(&self) -> bool {
+        self.default_value.is_some() || Self::is_option(&self.ty)
+    }
+
+    // Option<T>の引数はnilを受け付け、末尾にあれば省略もできる
+    pub fn is_option(ty: &syn::Type) -> bool {
+        if let syn::Type::Path(p) = ty {
+            if let Some(segment) = p.path.segments.last() {
+                return segment.ident == "Option";
+            }
+        }
+        false
+    }
 }
 
 #[derive(Debug)]
@@ -17,63 +54,402 @@ pub enum ArgumentDefaultValue {
     Nil,
     Boolean(proc_macro2::Ident),
     StringLiteral(proc_macro2::Literal),
-    NumberLiteral(proc_macro2::Literal),
+    Integer(i128),
+    Float(f64),
+    Symbol(String),
+    Array(Vec<ArgumentDefaultValue>),
+    Hash(Vec<(ArgumentDefaultValue, ArgumentDefaultValue)>),
+    // Float::INFINITY のようなRubyの定数
+    Constant(String),
+    // rust!(MY_CONST) のようなRustの式
+    Rust(proc_macro2::TokenStream),
+    // i64にもu64にも収まらない整数。validateでエラーにする
+    IntegerOutOfRange(proc_macro2::TokenStream),
+    // 解釈できなかった値。validateでエラーにする
+    Invalid(proc_macro2::TokenStream),
 }
 
 impl From<proc_macro2::TokenStream> for ArgumentDefaultValue {
     fn from(tokens: proc_macro2::TokenStream) -> ArgumentDefaultValue {
-        let s = tokens.into_iter().fold(String::new(), |mut acc, tree| {
-            let s = match tree {
-                proc_macro2::TokenTree::Ident(ident) => ident.to_string(),
-                proc_macro2::TokenTree::Literal(literal) => literal.to_string(),
-                proc_macro2::TokenTree::Punct(punct) => punct.to_string(),
-                _ => String::new(),
-            };
-            acc.push_str(&s);
-            acc
+        use proc_macro2::TokenTree::{Group, Ident, Literal, Punct};
+
+        let trees = tokens.clone().into_iter().collect::<Vec<proc_macro2::TokenTree>>();
+        match trees.as_slice() {
+            // [] で囲まれていたら配列、{} で囲まれていたらHash
+            [Group(g)] if g.delimiter() == proc_macro2::Delimiter::Bracket => Self::array_from(g.stream()),
+            [Group(g)] if g.delimiter() == proc_macro2::Delimiter::Brace => Self::hash_from(g.stream()),
+            [Ident(ident)] if ident == "nil" => Self::Nil,
+            [Ident(ident)] if ident == "true" || ident == "false" => Self::Boolean(ident.clone()),
+            [Literal(literal)] => Self::literal_from(literal, false).unwrap_or(Self::Invalid(tokens)),
+            // -1 や -1.5 は - と数値リテラルに分かれている
+            [Punct(p), Literal(literal)] if p.as_char() == '-' => {
+                Self::literal_from(literal, true).unwrap_or(Self::Invalid(tokens))
+            },
+            // :sym と :"sym"、:sym? :sym! :sym=
+            [Punct(p), Ident(ident)] if p.as_char() == ':' => Self::Symbol(ident.to_string()),
+            [Punct(p), Ident(ident), Punct(suffix)] if p.as_char() == ':' && ['?', '!', '='].contains(&suffix.as_char()) => {
+                Self::Symbol(format!("{}{}", ident, suffix.as_char()))
+            },
+            [Punct(p), Literal(literal)] if p.as_char() == ':' => match Self::literal_from(literal, false) {
+                Some(Self::StringLiteral(_)) => Self::Symbol(Self::unquote(literal)),
+                _ => Self::Invalid(tokens),
+            },
+            // rust!(MY_CONST)
+            [Ident(ident), Punct(p), Group(g)] if ident == "rust" && p.as_char() == '!' => Self::Rust(g.stream()),
+            _ => Self::constant_from(&trees).unwrap_or(Self::Invalid(tokens)),
+        }
+    }
+}
+
+impl ArgumentDefaultValue {
+    // 文字列、整数、浮動小数点数のリテラル
+    // 整数は 0xff や 1_000、1_i64 のようなRustの書き方も受け付ける
+    fn literal_from(literal: &proc_macro2::Literal, negative: bool) -> Option<ArgumentDefaultValue> {
+        match syn::Lit::new(literal.clone()) {
+            syn::Lit::Str(s) if !negative => Some(Self::StringLiteral(proc_macro2::Literal::string(&s.value()))),
+            syn::Lit::Int(i) => {
+                let n = i.base10_parse::<i128>().ok().map(|n| if negative { -n } else { n });
+                match n {
+                    Some(n) if (i128::from(i64::MIN)..=i128::from(u64::MAX)).contains(&n) => Some(Self::Integer(n)),
+                    _ => Some(Self::IntegerOutOfRange(proc_macro2::TokenTree::Literal(literal.clone()).into())),
+                }
+            },
+            syn::Lit::Float(f) => {
+                let f = f.base10_parse::<f64>().ok().map(|f| if negative { -f } else { f })?;
+                if f.is_finite() { Some(Self::Float(f)) } else { None }
+            },
+            _ => None,
+        }
+    }
+
+    fn unquote(literal: &proc_macro2::Literal) -> String {
+        syn::parse_str::<syn::LitStr>(&literal.to_string())
+            .map(|s| s.value())
+            .unwrap_or_default()
+    }
+
+    // Float::INFINITY や Foo::BAR のように大文字で始まる名前を :: でつないだもの
+    fn constant_from(trees: &[proc_macro2::TokenTree]) -> Option<ArgumentDefaultValue> {
+        let mut names = Vec::new();
+        let mut rest = trees;
+        loop {
+            match rest {
+                [proc_macro2::TokenTree::Ident(ident), tail @ ..] if ident.to_string().starts_with(|c: char| c.is_ascii_uppercase()) => {
+                    names.push(ident.to_string());
+                    rest = tail;
+                },
+                _ => return None,
+            }
+            match rest {
+                [] => return Some(Self::Constant(names.join("::"))),
+                [proc_macro2::TokenTree::Punct(p1), proc_macro2::TokenTree::Punct(p2), tail @ ..] if p1.as_char() == ':' && p2.as_char() == ':' => {
+                    rest = tail;
+                },
+                _ => return None,
+            }
+        }
+    }
+
+    fn split_by_comma(tokens: proc_macro2::TokenStream) -> Vec<Vec<proc_macro2::TokenTree>> {
+        tokens
+            .into_iter()
+            .collect::<Vec<proc_macro2::TokenTree>>()
+            .split(|tree| matches!(tree, proc_macro2::TokenTree::Punct(p) if p.as_char() == ','))
+            .filter(|v| !v.is_empty())
+            .map(|v| v.to_vec())
+            .collect()
+    }
+
+    // [1, "a", nil] のカンマ区切りの中身を要素ごとに変換する
+    fn array_from(tokens: proc_macro2::TokenStream) -> ArgumentDefaultValue {
+        let elements = Self::split_by_comma(tokens)
+            .into_iter()
+            .map(|v| Self::from(proc_macro2::TokenStream::from_iter(v)))
+            .collect();
+        Self::Array(elements)
+    }
+
+    // { a: 1, "b": 2, "c" => 3 } のカンマ区切りの中身をキーと値のペアに変換する
+    fn hash_from(tokens: proc_macro2::TokenStream) -> ArgumentDefaultValue {
+        let entries = Self::split_by_comma(tokens)
+            .into_iter()
+            .map(|entry| Self::hash_entry_from(&entry))
+            .collect();
+        Self::Hash(entries)
+    }
+
+    // 解釈できなかったエントリーはキーをInvalidにしてvalidateでエラーにする
+    fn hash_entry_from(entry: &[proc_macro2::TokenTree]) -> (ArgumentDefaultValue, ArgumentDefaultValue) {
+        use proc_macro2::TokenTree::{Ident, Literal, Punct};
+
+        let value_from = |value: &[proc_macro2::TokenTree]| Self::from(proc_macro2::TokenStream::from_iter(value.to_vec()));
+        let is_colon = |p: &proc_macro2::Punct| p.as_char() == ':' && p.spacing() == proc_macro2::Spacing::Alone;
+        let arrow = entry.windows(2).position(|w| {
+            matches!(&w[0], Punct(p) if p.as_char() == '=' && p.spacing() == proc_macro2::Spacing::Joint)
+                && matches!(&w[1], Punct(p) if p.as_char() == '>')
         });
-        match s.as_str() {
-            "nil" => Self::Nil,
-            "true" => Self::Boolean(quote::format_ident!("true")),
-            "false" => Self::Boolean(quote::format_ident!("false")),
-            _ => {
-                let l = s.to_string();
-                let trimed = l.trim_matches('"').to_string();
-                // 文字列の両端がダブルクオーテーションで囲まれていたらStringLiteral,
-                // 囲まれていなかったら数値リテラル
-                if l == trimed {
-                    if let Ok(n) = l.parse::<i128>() {
-                        Self::NumberLiteral(proc_macro2::Literal::i128_suffixed(n))
-                    } else {
-                        Self::StringLiteral(proc_macro2::Literal::string(&l))
-                    }
+
+        match entry {
+            // a: 1
+            [Ident(key), Punct(p), value @ ..] if is_colon(p) && !value.is_empty() => (Self::Symbol(key.to_string()), value_from(value)),
+            // "b": 2 はSymbolのキー
+            [Literal(key), Punct(p), value @ ..] if is_colon(p) && !value.is_empty() && key.to_string().starts_with('"') => {
+                (Self::Symbol(Self::unquote(key)), value_from(value))
+            },
+            // "c" => 3
+            _ => match arrow {
+                Some(arrow) if arrow > 0 && arrow + 2 < entry.len() => (value_from(&entry[..arrow]), value_from(&entry[arrow + 2..])),
+                // { :a } や { 1 } のようにキーと値の組になっていない
+                _ => (Self::Invalid(proc_macro2::TokenStream::from_iter(entry.to_vec())), Self::Nil),
+            },
+        }
+    }
+
+    // 解釈できなかったデフォルト値はコンパイルエラーにする
+    pub fn validate(&self) -> syn::Result<()> {
+        match self {
+            Self::Invalid(tokens) => Err(syn::Error::new_spanned(
+                tokens,
+                "unsupported default value. Use nil, true, false, a number, a string, a :symbol, an array, a hash, a Ruby constant or rust!(expr).",
+            )),
+            Self::IntegerOutOfRange(tokens) => Err(syn::Error::new_spanned(
+                tokens,
+                format!("integer default value is out of range. Use a value between {} and {}.", i64::MIN, u64::MAX),
+            )),
+            Self::Array(elements) => elements.iter().try_for_each(|e| e.validate()),
+            Self::Hash(entries) => entries.iter().try_for_each(|(k, v)| k.validate().and(v.validate())),
+            _ => Ok(()),
+        }
+    }
+
+    // Rubyでの値のクラス。Rubyの定数とRustの式はマクロでは分からない
+    fn class_name(&self) -> Option<&'static str> {
+        match self {
+            Self::Nil => Some("nil"),
+            Self::Boolean(_) => Some("true or false"),
+            Self::StringLiteral(_) => Some("String"),
+            Self::Integer(_) => Some("Integer"),
+            Self::Float(_) => Some("Float"),
+            Self::Symbol(_) => Some("Symbol"),
+            Self::Array(_) => Some("Array"),
+            Self::Hash(_) => Some("Hash"),
+            Self::Constant(_) | Self::Rust(_) | Self::IntegerOutOfRange(_) | Self::Invalid(_) => None,
+        }
+    }
+
+    // 型の最後のセグメントの名前とジェネリクスの型引数
+    fn type_segment(ty: &syn::Type) -> Option<(String, Vec<syn::Type>)> {
+        if let syn::Type::Path(p) = ty {
+            let segment = p.path.segments.last()?;
+            let args = if let syn::PathArguments::AngleBracketed(a) = &segment.arguments {
+                a.args
+                    .iter()
+                    .filter_map(|arg| if let syn::GenericArgument::Type(t) = arg { Some(t.clone()) } else { None })
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            return Some((segment.ident.to_string(), args));
+        }
+        None
+    }
+
+    // Errは変換できない。Rubyの定数とRustの式、マクロで判定できない型はOk
+    pub fn check_type(&self, ty: &syn::Type) -> Result<(), String> {
+        let class_name = match self.class_name() {
+            Some(class_name) => class_name,
+            None => return Ok(()),
+        };
+        let (name, args) = match Self::type_segment(ty) {
+            Some(segment) => segment,
+            None => return Ok(()),
+        };
+        let expected: &[&str] = match name.as_str() {
+            "AnyObject" => return Ok(()),
+            "Option" => {
+                return match (self, args.first()) {
+                    (Self::Nil, _) | (_, None) => Ok(()),
+                    (_, Some(inner)) => self.check_type(inner),
+                };
+            },
+            // 暗黙の型変換はデフォルト値と同じ型のときは呼ばれない
+            "Coerce" => {
+                return match args.first() {
+                    Some(inner) => self.check_type(inner),
+                    None => Ok(()),
+                };
+            },
+            "Vec" => {
+                if let (Self::Array(elements), Some(inner)) = (self, args.first()) {
+                    return elements
+                        .iter()
+                        .try_for_each(|e| e.check_type(inner))
+                        .map_err(|message| format!("{} in an array element", message));
+                }
+                &["Array"]
+            },
+            "HashMap" | "BTreeMap" => {
+                if let (Self::Hash(entries), Some(key), Some(value)) = (self, args.first(), args.get(1)) {
+                    return entries.iter().try_for_each(|(k, v)| {
+                        k.check_key_type(key).map_err(|message| format!("{} in a hash key", message))?;
+                        v.check_type(value).map_err(|message| format!("{} in a hash value", message))
+                    });
+                }
+                &["Hash"]
+            },
+            "RString" | "String" => &["String"],
+            "Fixnum" | "Integer" | "i8" | "i16" | "i32" | "i64" | "isize" | "u8" | "u16" | "u32" | "u64" | "usize" => &["Integer"],
+            "Float" => &["Float"],
+            // f32とf64はIntegerも受け取る
+            "f32" | "f64" => &["Float", "Integer"],
+            "Boolean" | "bool" => &["true or false"],
+            "Symbol" => &["Symbol"],
+            "NilClass" => &["nil"],
+            "Array" => &["Array"],
+            "Hash" => &["Hash"],
+            _ => return Ok(()),
+        };
+        if expected.contains(&class_name) {
+            Ok(())
+        } else {
+            Err(format!("`{}` expects {}, got {}", name, expected.join(" or "), class_name))
+        }
+    }
+
+    // Hashのキーは FromHashKey で変換する。StringはSymbolのキーも受け付ける
+    fn check_key_type(&self, ty: &syn::Type) -> Result<(), String> {
+        let class_name = match self.class_name() {
+            Some(class_name) => class_name,
+            None => return Ok(()),
+        };
+        match Self::type_segment(ty) {
+            Some((name, _)) if name == "String" || name == "HashKey" => {
+                if class_name == "String" || class_name == "Symbol" {
+                    Ok(())
                 } else {
-                    Self::StringLiteral(proc_macro2::Literal::string(&trimed))
+                    Err(format!("`{}` expects String or Symbol, got {}", name, class_name))
                 }
             },
+            _ => Ok(()),
         }
     }
-}
 
-impl ArgumentDefaultValue {
-    pub fn to_default_value(&self, ty: &syn::Type) -> proc_macro2::TokenStream {
+    // rust!(expr) の式の型が引数の型に Into で変換できることを、生成したコードでチェックする
+    // Into の無い型 (Fixnumや独自の型など) は、呼び出したときにFromRubyで変換する
+    pub fn type_assertions(&self, ty: &syn::Type) -> Vec<proc_macro2::TokenStream> {
+        let (name, args) = match Self::type_segment(ty) {
+            Some(segment) => segment,
+            None => return Vec::new(),
+        };
+        match (self, name.as_str(), args.first()) {
+            (Self::Rust(expr), _, _) if Self::is_assertable(ty) => {
+                let span = syn::spanned::Spanned::span(expr);
+                vec![quote::quote_spanned! { span =>
+                    const _: fn() -> #ty = || ::std::convert::Into::into(#expr);
+                }]
+            },
+            (_, "Option", Some(inner)) | (_, "Coerce", Some(inner)) => self.type_assertions(inner),
+            (Self::Array(elements), "Vec", Some(inner)) => elements.iter().flat_map(|e| e.type_assertions(inner)).collect(),
+            (Self::Hash(entries), "HashMap", _) | (Self::Hash(entries), "BTreeMap", _) => match args.get(1) {
+                Some(value) => entries.iter().flat_map(|(_, v)| v.type_assertions(value)).collect(),
+                None => Vec::new(),
+            },
+            _ => Vec::new(),
+        }
+    }
+
+    // Into で変換できる値が、Rubyのオブジェクトにしてから同じ型に戻せる型
+    fn is_assertable(ty: &syn::Type) -> bool {
+        match Self::type_segment(ty) {
+            Some((name, args)) => match name.as_str() {
+                "Option" | "Vec" => args.first().is_some_and(Self::is_assertable),
+                "i8" | "i16" | "i32" | "i64" | "isize" | "u8" | "u16" | "u32" | "u64" | "usize" | "f32" | "f64" | "bool" | "String" | "Integer" | "RString" => true,
+                _ => false,
+            },
+            None => false,
+        }
+    }
+
+    // Rubyの定数を含むデフォルト値は、定数を探すクラス名 CLASS_NAME を使う
+    pub fn has_constant(&self) -> bool {
+        match self {
+            Self::Constant(_) => true,
+            Self::Array(elements) => elements.iter().any(|e| e.has_constant()),
+            Self::Hash(entries) => entries.iter().any(|(k, v)| k.has_constant() || v.has_constant()),
+            _ => false,
+        }
+    }
+
+    pub fn to_default_value(&self) -> proc_macro2::TokenStream {
+        self.to_element_value()
+    }
+
+    // 引数の型に関係なくRubyのオブジェクトにする。型の変換は引数を受け取るときに行う
+    fn to_element_value(&self) -> proc_macro2::TokenStream {
         match self {
-            Self::Nil => quote::quote! { None },
-            Self::Boolean(b) => {
-                let b = quote::format_ident!("{}", b);
-                quote::quote! { Boolean::new(#b) }
+            Self::Nil => quote::quote! { rutie::NilClass::new() },
+            Self::Boolean(b) => quote::quote! { rutie::Boolean::new(#b) },
+            Self::StringLiteral(s) => quote::quote! { rutie::RString::new_utf8(#s) },
+            // i64に収まらない正の整数はu64から作る
+            Self::Integer(n) if *n > i128::from(i64::MAX) => {
+                let n = proc_macro2::Literal::u64_suffixed(*n as u64);
+                quote::quote! { rutie::Integer::from(#n) }
             },
-            Self::StringLiteral(s) => quote::quote! { RString::from(#s) },
-            Self::NumberLiteral(n) => quote::quote! { #ty::new(#n) },
+            Self::Integer(n) => {
+                let abs = proc_macro2::Literal::i128_unsuffixed(n.abs());
+                if *n < 0 {
+                    quote::quote! { rutie::Integer::new(-#abs) }
+                } else {
+                    quote::quote! { rutie::Integer::new(#abs) }
+                }
+            },
+            Self::Float(f) => {
+                let abs = proc_macro2::Literal::f64_unsuffixed(f.abs());
+                if *f < 0.0 {
+                    quote::quote! { rutie::Float::new(-#abs) }
+                } else {
+                    quote::quote! { rutie::Float::new(#abs) }
+                }
+            },
+            Self::Symbol(s) => quote::quote! { rutie::Symbol::new(#s) },
+            Self::Array(elements) => {
+                let len = elements.len();
+                let elements = elements.iter().map(|e| e.to_element_value());
+                quote::quote! {
+                    {
+                        let mut array = rutie::Array::with_capacity(#len);
+                        #(array.push(#elements);)*
+                        array
+                    }
+                }
+            },
+            Self::Hash(entries) => {
+                let keys = entries.iter().map(|(k, _)| k.to_element_value());
+                let values = entries.iter().map(|(_, v)| v.to_element_value());
+                quote::quote! {
+                    {
+                        let mut hash = rutie::Hash::new();
+                        #(hash.store(#keys, #values);)*
+                        hash
+                    }
+                }
+            },
+            // 定数が無いときのNameErrorは引数の変換エラーと同じく呼び出し前に返す
+            Self::Constant(name) => quote::quote! { rutie_attr_backend::constant_value(CLASS_NAME, #name)? },
+            Self::Rust(expr) => quote::quote! { rutie_attr_backend::ToDefaultValue::to_default_value(&(#expr)) },
+            Self::IntegerOutOfRange(_) | Self::Invalid(_) => unreachable!("invalid default value is rejected by validate"),
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ArgumentKind {
     Arg,
     DArg,
     KwArg,
+    Splat,
+    DoubleSplat,
+    Block,
 }
 
 impl ArgumentKind {
@@ -82,16 +458,191 @@ impl ArgumentKind {
             Self::Arg => syn::parse_quote! { rutie_attr_backend::Arg<#ty> },
             Self::DArg => syn::parse_quote! { rutie_attr_backend::DArg<#ty> },
             Self::KwArg => syn::parse_quote! { rutie_attr_backend::KwArg<#ty> },
+            Self::Splat => syn::parse_quote! { rutie_attr_backend::SplatArg<#ty> },
+            Self::DoubleSplat => syn::parse_quote! { rutie_attr_backend::DoubleSplatArg<#ty> },
+            Self::Block => syn::parse_quote! { rutie_attr_backend::BlockArg<#ty> },
         }
     }
 
-    pub fn expr_call_for_initialize_struct_field(&self, index: usize, field_name: &str) -> syn::Expr {
-        // a: Arg::from_arg(_arguments.get(0)),
-        //    ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+    pub fn expr_call_for_initialize_struct_field(&self, position: &ArgumentPosition, field_name: &str, ty: &syn::Type, default_value: Option<&ArgumentDefaultValue>) -> syn::Expr {
+        // a: Arg::from_arg(_arguments.required(0), "a", METHOD_NAME),
+        //    ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+        let source = position.source_expr();
+        // 引数の型が変換に対応していないときのエラーは、その引数の型を指す
+        let span = syn::spanned::Spanned::span(ty);
+        // デフォルト値は引数が省略されたときだけ作る
+        let default_value = default_value.map(|default_value| -> syn::Expr {
+            let default_value = default_value.to_default_value();
+            syn::parse_quote! { || -> Result<rutie::AnyObject, rutie::AnyException> { Ok(rutie::Object::to_any_object(&#default_value)) } }
+        });
         match &self {
-            Self::Arg => syn::parse_quote! { rutie_attr_backend::Arg::from_arg(_arguments.get(#index)) },
-            Self::DArg => syn::parse_quote! { rutie_attr_backend::DArg::from_arg_with_default(_arguments.get(#index), default_value_map.get(#field_name)) },
-            Self::KwArg => syn::parse_quote! { rutie_attr_backend::KwArg::from_arg_with_key_and_default(_arguments.get(#index), #field_name, def_signature_map.get(#field_name)) },
+            Self::Arg => syn::parse_quote_spanned! { span => rutie_attr_backend::Arg::from_arg(#source, #field_name, METHOD_NAME) },
+            Self::DArg => {
+                let default_value = default_value.expect("optional argument has a default value");
+                syn::parse_quote_spanned! { span => rutie_attr_backend::DArg::from_arg_with_default(#source, #field_name, METHOD_NAME, #default_value) }
+            },
+            Self::KwArg => {
+                let key = keyword_name_ident(field_name);
+                let default_value: syn::Expr = match default_value {
+                    Some(default_value) => syn::parse_quote! { Some(#default_value) },
+                    None => syn::parse_quote! { None::<fn() -> Result<rutie::AnyObject, rutie::AnyException>> },
+                };
+                syn::parse_quote_spanned! { span => rutie_attr_backend::KwArg::from_arg_with_key_and_default(#source, &#key, METHOD_NAME, #default_value) }
+            },
+            Self::Splat => syn::parse_quote_spanned! { span => rutie_attr_backend::SplatArg::from_splat_arg(#source, #field_name, METHOD_NAME) },
+            Self::DoubleSplat => syn::parse_quote_spanned! { span => rutie_attr_backend::DoubleSplatArg::from_double_splat_arg(#source, #field_name, METHOD_NAME) },
+            Self::Block => syn::parse_quote_spanned! { span => rutie_attr_backend::BlockArg::from_block_arg(#source, #field_name) },
         }
     }
 }
+
+// Rubyの引数のどこから値を取るか
+#[derive(Debug)]
+pub enum ArgumentPosition {
+    Required(usize),
+    Optional(usize),
+    Splat,
+    Post(usize),
+    Keyword,
+    // 名前付きのキーワード引数以外のキーワード
+    DoubleSplat(Vec<String>),
+    Block,
+}
+
+impl ArgumentPosition {
+    fn source_expr(&self) -> syn::Expr {
+        match self {
+            Self::Required(i) => syn::parse_quote! { _arguments.required(#i) },
+            Self::Optional(i) => syn::parse_quote! { _arguments.optional(#i) },
+            Self::Splat => syn::parse_quote! { _arguments.splat() },
+            Self::Post(i) => syn::parse_quote! { _arguments.post(#i) },
+            Self::Keyword => syn::parse_quote! { _arguments.keywords() },
+            Self::DoubleSplat(known) => {
+                let known = known.iter().map(|name| keyword_name_ident(name));
+                syn::parse_quote! { _arguments.rest_keywords(&[#(&#known),*]) }
+            },
+            Self::Block => syn::parse_quote! { _arguments.block() },
+        }
+    }
+}
+
+// キーワード引数の名前をキャッシュするstaticの名前
+pub fn keyword_name_ident(name: &str) -> proc_macro2::Ident {
+    quote::format_ident!("KEYWORD_{}", name.to_uppercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArgumentDefaultValue;
+
+    fn check(default_value: proc_macro2::TokenStream, ty: syn::Type) -> Result<(), String> {
+        ArgumentDefaultValue::from(default_value).check_type(&ty)
+    }
+
+    #[test]
+    fn accepts_matching_ruby_classes() {
+        assert_eq!(check(quote::quote! { 1 }, syn::parse_quote! { Integer }), Ok(()));
+        assert_eq!(check(quote::quote! { -1 }, syn::parse_quote! { i32 }), Ok(()));
+        assert_eq!(check(quote::quote! { 1.5 }, syn::parse_quote! { f32 }), Ok(()));
+        assert_eq!(check(quote::quote! { 1 }, syn::parse_quote! { f64 }), Ok(()));
+        assert_eq!(check(quote::quote! { "a" }, syn::parse_quote! { String }), Ok(()));
+        assert_eq!(check(quote::quote! { true }, syn::parse_quote! { bool }), Ok(()));
+        assert_eq!(check(quote::quote! { :a }, syn::parse_quote! { Symbol }), Ok(()));
+        assert_eq!(check(quote::quote! { "a" }, syn::parse_quote! { AnyObject }), Ok(()));
+    }
+
+    #[test]
+    fn rejects_mismatched_ruby_classes() {
+        assert_eq!(
+            check(quote::quote! { "a" }, syn::parse_quote! { i64 }),
+            Err(String::from("`i64` expects Integer, got String")),
+        );
+        assert_eq!(
+            check(quote::quote! { 1 }, syn::parse_quote! { Float }),
+            Err(String::from("`Float` expects Float, got Integer")),
+        );
+        assert!(check(quote::quote! { nil }, syn::parse_quote! { RString }).is_err());
+    }
+
+    #[test]
+    fn checks_inner_types() {
+        assert_eq!(check(quote::quote! { nil }, syn::parse_quote! { Option<i64> }), Ok(()));
+        assert_eq!(check(quote::quote! { 1 }, syn::parse_quote! { Option<i64> }), Ok(()));
+        assert_eq!(check(quote::quote! { 1 }, syn::parse_quote! { Coerce<Integer> }), Ok(()));
+        assert!(check(quote::quote! { "a" }, syn::parse_quote! { Option<i64> }).is_err());
+        assert_eq!(check(quote::quote! { [1, 2] }, syn::parse_quote! { Vec<i64> }), Ok(()));
+        assert_eq!(
+            check(quote::quote! { [1, "a"] }, syn::parse_quote! { Vec<i64> }),
+            Err(String::from("`i64` expects Integer, got String in an array element")),
+        );
+    }
+
+    #[test]
+    fn checks_hash_keys_and_values() {
+        assert_eq!(check(quote::quote! { { a: 1, "b" => 2 } }, syn::parse_quote! { HashMap<String, i64> }), Ok(()));
+        assert_eq!(check(quote::quote! { { a: 1 } }, syn::parse_quote! { BTreeMap<HashKey, i64> }), Ok(()));
+        assert_eq!(
+            check(quote::quote! { { 1 => 1 } }, syn::parse_quote! { HashMap<String, i64> }),
+            Err(String::from("`String` expects String or Symbol, got Integer in a hash key")),
+        );
+        assert_eq!(
+            check(quote::quote! { { a: "1" } }, syn::parse_quote! { HashMap<String, i64> }),
+            Err(String::from("`i64` expects Integer, got String in a hash value")),
+        );
+    }
+
+    #[test]
+    fn leaves_unknown_types_and_constants_to_runtime() {
+        assert_eq!(check(quote::quote! { 1 }, syn::parse_quote! { Even }), Ok(()));
+        assert_eq!(check(quote::quote! { Float::INFINITY }, syn::parse_quote! { i64 }), Ok(()));
+        assert_eq!(check(quote::quote! { rust!(LIMIT) }, syn::parse_quote! { i64 }), Ok(()));
+    }
+
+    fn assertions(default_value: proc_macro2::TokenStream, ty: syn::Type) -> Vec<String> {
+        ArgumentDefaultValue::from(default_value)
+            .type_assertions(&ty)
+            .iter()
+            .map(|tokens| tokens.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn asserts_rust_expressions_for_known_types() {
+        assert_eq!(
+            assertions(quote::quote! { rust!(LIMIT) }, syn::parse_quote! { i64 }),
+            vec![quote::quote! { const _: fn() -> i64 = || ::std::convert::Into::into(LIMIT); }.to_string()],
+        );
+        assert_eq!(
+            assertions(quote::quote! { rust!(LIMIT) }, syn::parse_quote! { Coerce<Integer> }),
+            vec![quote::quote! { const _: fn() -> Integer = || ::std::convert::Into::into(LIMIT); }.to_string()],
+        );
+        assert_eq!(assertions(quote::quote! { [1, rust!(LIMIT)] }, syn::parse_quote! { Vec<i64> }).len(), 1);
+        assert_eq!(assertions(quote::quote! { { a: rust!(LIMIT) } }, syn::parse_quote! { HashMap<String, i64> }).len(), 1);
+    }
+
+    #[test]
+    fn does_not_assert_types_without_into() {
+        assert!(assertions(quote::quote! { rust!(LIMIT) }, syn::parse_quote! { Fixnum }).is_empty());
+        assert!(assertions(quote::quote! { rust!(LIMIT) }, syn::parse_quote! { Even }).is_empty());
+        assert!(assertions(quote::quote! { 1 }, syn::parse_quote! { i64 }).is_empty());
+    }
+
+    #[test]
+    fn parses_rust_integer_literals_and_symbol_suffixes() {
+        assert!(matches!(ArgumentDefaultValue::from(quote::quote! { 0xff }), ArgumentDefaultValue::Integer(255)));
+        assert!(matches!(ArgumentDefaultValue::from(quote::quote! { -1_000_i64 }), ArgumentDefaultValue::Integer(-1000)));
+        assert!(matches!(
+            ArgumentDefaultValue::from(quote::quote! { 0xFFFF_FFFF_FFFF_FFFF }),
+            ArgumentDefaultValue::Integer(n) if n == i128::from(u64::MAX)
+        ));
+        assert!(matches!(ArgumentDefaultValue::from(quote::quote! { :empty? }), ArgumentDefaultValue::Symbol(s) if s == "empty?"));
+        assert!(matches!(ArgumentDefaultValue::from(quote::quote! { :name= }), ArgumentDefaultValue::Symbol(s) if s == "name="));
+    }
+
+    #[test]
+    fn rejects_integers_out_of_range() {
+        assert!(ArgumentDefaultValue::from(quote::quote! { -0x8000_0000_0000_0000 }).validate().is_ok());
+        assert!(ArgumentDefaultValue::from(quote::quote! { 18446744073709551616 }).validate().is_err());
+        assert!(ArgumentDefaultValue::from(quote::quote! { -9223372036854775809 }).validate().is_err());
+    }
+}
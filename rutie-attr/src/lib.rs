@@ -2,7 +2,9 @@ mod rbclass;
 mod rbdef;
 mod rbmethods;
 mod method;
+mod method_name;
 mod argument;
+mod signature;
 mod util;
 
 use once_cell::sync::Lazy;
@@ -14,8 +16,17 @@ use proc_macro::TokenStream;
 use std::convert::From;
 use syn::{parse_macro_input, ItemFn, ItemImpl, ItemStruct};
 
+// 構造体のフィールドの (フィールド名, 型のトークン列)
+type ClassFields = Vec<(String, String)>;
+
 // rbclassで解析した構造体のフィールド情報をrbmethodsで使うためのグローバル変数
-static DEFINED_CLASSES: Lazy<Mutex<HashMap<String, Vec<String>>>> = Lazy::new(|| {
+static DEFINED_CLASSES: Lazy<Mutex<HashMap<String, ClassFields>>> = Lazy::new(|| {
+    let m = HashMap::new();
+    Mutex::new(m)
+});
+
+// rbmethodsを展開したクラスと、そのimplに書かれた関数名
+static DEFINED_METHODS: Lazy<Mutex<HashMap<String, Vec<String>>>> = Lazy::new(|| {
     let m = HashMap::new();
     Mutex::new(m)
 });
@@ -1,14 +1,25 @@
-use super::argument::{Argument, ArgumentKind};
+use super::argument::{keyword_name_ident, Argument, ArgumentKind, ArgumentPosition};
+use super::method_name::MethodNameKind;
+use super::signature::Signature;
 use std::collections::HashMap;
-use super::util::{combined_errors, uppercase_first_letter};
-use std::iter::FromIterator;
+use super::util::{combined_errors, field_reader_ident, uppercase_first_letter};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum MethodKind {
     Static,
     Instance,
 }
 
+// Rubyでのメソッドの可視性
+#[derive(Debug, PartialEq)]
+pub enum Visibility {
+    Public,
+    Private,
+    Protected,
+    // 特異メソッドと、同じ名前のprivateなインスタンスメソッドを定義する
+    ModuleFunction,
+}
+
 #[derive(Debug)]
 pub struct Method {
     pub fn_name: proc_macro2::Ident,
@@ -16,7 +27,16 @@ pub struct Method {
     pub return_type: syn::ReturnType,
     pub arguments: Vec<Argument>,
     pub def_name: proc_macro2::TokenStream,
-    pub def_signature_map: HashMap<String, Vec<proc_macro2::TokenTree>>,
+    pub def_signature: Signature,
+    pub visibility: Visibility,
+    // alias = "..." で追加するRubyでの別名
+    pub aliases: Vec<syn::LitStr>,
+    // インスタンスメソッドが self.field で読むフィールドと型。self をそれ以外に使うときはNone
+    pub self_fields: Option<Vec<(proc_macro2::Ident, syn::Type)>>,
+    // impl内に書かれたメソッドそのもの
+    pub item: syn::ImplItemMethod,
+    // rbdef attributeのオプションのエラー
+    pub attribute_errors: Vec<syn::Error>,
 }
 
 impl Method {
@@ -49,22 +69,70 @@ struct RutieFooTestMethod {
     c: KwArg<RString>,
     d: KwArg<RString>,
     e: KwArg<RString>,
+    argument_error: Result<(), AnyException>,
 }
 */
         let struct_name = self.method_struct_name(rutie_class);
         let mut punct = syn::punctuated::Punctuated::new();
         punct.push(self.method_struct_impl_field_rtself_expr(rutie_class));
-        let punct = self.method_struct_fields().iter().fold(punct, |mut acc, f| {
+        let mut punct = self.method_struct_fields().iter().fold(punct, |mut acc, f| {
             acc.push(f.clone());
             acc
         });
+        let field: syn::FieldsNamed = syn::parse_quote! { { argument_error: Result<(), rutie::AnyException> } };
+        punct.extend(field.named);
         let span = proc_macro2::Span::call_site();
         let fields_named = syn::FieldsNamed { brace_token: syn::token::Brace(span), named: punct };
         let fields = syn::Fields::Named(fields_named);
-        let mut item: syn::ItemStruct = syn::parse_quote! { struct #struct_name {} };
+        // 構造体名は関数名そのままなので、foo_bar なら RutieFooFoo_barMethod になる
+        let mut item: syn::ItemStruct = syn::parse_quote! {
+            #[allow(non_camel_case_types)]
+            struct #struct_name {}
+        };
         item.fields = fields;
+        let fields_struct = self.fields_struct(rutie_class);
+
+        quote::quote! {
+            #item
+            #fields_struct
+        }
+    }
 
-        quote::quote! { #item }
+    // 使うフィールドだけを変換して呼べるインスタンスメソッド
+    pub fn uses_fields_struct(&self) -> bool {
+        self.kind == MethodKind::Instance && self.self_fields.is_some()
+    }
+
+    fn fields_struct_name(&self, rutie_class: &proc_macro2::Ident) -> proc_macro2::Ident {
+        quote::format_ident!("{}{}Fields", rutie_class, uppercase_first_letter(&self.fn_name.to_string()))
+    }
+
+    // メソッドが読むフィールドだけを持つ構造体に、同じメソッドを定義する
+    // 使わないフィールドはインスタンス変数から読まないので、nilや別の型でも呼べる
+    fn fields_struct(&self, rutie_class: &proc_macro2::Ident) -> Option<proc_macro2::TokenStream> {
+        if !self.uses_fields_struct() {
+            return None;
+        }
+        let struct_name = self.fields_struct_name(rutie_class);
+        let (names, types): (Vec<_>, Vec<_>) = self.self_fields.clone()?.into_iter().unzip();
+        let mut item = self.item.clone();
+        item.attrs.retain(|attr| !attr.path.is_ident("rbdef") && !attr.path.is_ident("rbdefself"));
+        item.vis = syn::Visibility::Inherited;
+        Some(quote::quote! {
+            #[allow(non_camel_case_types)]
+            struct #struct_name {
+                #(#names: #types),*
+            }
+
+            impl #struct_name {
+                #item
+            }
+        })
+    }
+
+    // &mut self のメソッドは変数をmutにして呼ぶ
+    fn takes_mut_self(&self) -> bool {
+        matches!(self.item.sig.receiver(), Some(syn::FnArg::Receiver(r)) if r.reference.is_some() && r.mutability.is_some())
     }
 
     fn method_struct_impl_field_rtself_expr(&self, rutie_class: &proc_macro2::Ident) -> syn::Field {
@@ -84,12 +152,12 @@ struct RutieFooTestMethod {
         self.arguments
             .iter()
             .map(|arg| {
-                let order = orders[&arg.name];
+                let order = &orders[&arg.name];
                 syn::FieldValue {
                     attrs: Vec::new(),
                     member: syn::Member::Named(arg.name.ident.clone()),
                     colon_token: Some(colon),
-                    expr: arg.kind.expr_call_for_initialize_struct_field(order, &arg.name.ident.to_string()),
+                    expr: arg.kind.expr_call_for_initialize_struct_field(order, &arg.name.ident.to_string(), &arg.ty, arg.default_value.as_ref()),
                 }
             })
             .collect()
@@ -109,27 +177,59 @@ struct RutieFooTestMethod {
 /*
 Self {
     rtself: RutieFoo,
-    a: Arg::from_arg(_arguments.get(0)),
-    b: DArg::from_arg_with_default(_arguments.get(1), default_value_map.get("b")),
-    c: KwArg::from_arg_with_key_and_default(_arguments.get(2), "c", default_value_map.get("c")),
-    d: KwArg::from_arg_with_key_and_default(_arguments.get(2), "d", default_value_map.get("d")),
-    e: KwArg::from_arg_with_key_and_default(_arguments.get(2), "e", default_value_map.get("e")),
+    a: Arg::from_arg(_arguments.required(0), "a", METHOD_NAME),
+    b: DArg::from_arg_with_default(_arguments.optional(0), "b", METHOD_NAME, || -> Result<..> { Ok(rutie::Object::to_any_object(&rutie::RString::new_utf8("b"))) }),
+    c: KwArg::from_arg_with_key_and_default(_arguments.keywords(), &KEYWORD_C, METHOD_NAME, None::<fn() -> Result<..>>),
+    d: KwArg::from_arg_with_key_and_default(_arguments.keywords(), &KEYWORD_D, METHOD_NAME, Some(|| ...)),
+    e: KwArg::from_arg_with_key_and_default(_arguments.keywords(), &KEYWORD_E, METHOD_NAME, Some(|| ...)),
+    argument_error: _arguments.arity(1, Some(2), &[&KEYWORD_C])
+        .and_then(|_| _arguments.missing_keywords(&[&KEYWORD_C]))
+        .and_then(|_| _arguments.unknown_keywords(&[&KEYWORD_C, &KEYWORD_D, &KEYWORD_E])),
 }
 */
+        let argument_error = self.argument_error_expr();
         let mut punct = syn::punctuated::Punctuated::new();
         punct.push(self.method_struct_impl_field_value_rtself_expr());
-        let punct = self.method_struct_impl_field_value_exprs().iter().fold(punct, |mut acc, f| {
+        let mut punct = self.method_struct_impl_field_value_exprs().iter().fold(punct, |mut acc, f| {
             acc.push(f.clone());
             acc
         });
+        punct.push(syn::parse_quote! { argument_error: #argument_error });
         let mut expr: syn::ExprStruct = syn::parse_quote! { Self {} };
         expr.fields = punct;
 
         expr
     }
 
+    // 引数の個数やキーワードのArgumentErrorは、Rubyと同じく個々の引数の変換より先に報告する
+    fn argument_error_expr(&self) -> syn::Expr {
+        let (minimum, maximum) = self.arity_range();
+        let maximum: syn::Expr = match maximum {
+            Some(maximum) => syn::parse_quote! { Some(#maximum) },
+            None => syn::parse_quote! { None },
+        };
+        let required_keywords = self.required_keyword_names()
+            .iter()
+            .map(|name| keyword_name_ident(name))
+            .collect::<Vec<proc_macro2::Ident>>();
+        let mut expr: syn::Expr = syn::parse_quote! { _arguments.arity(#minimum, #maximum, &[#(&#required_keywords),*]) };
+        if !required_keywords.is_empty() {
+            expr = syn::parse_quote! { #expr.and_then(|_| _arguments.missing_keywords(&[#(&#required_keywords),*])) };
+        }
+        if self.checks_unknown_keywords() {
+            let known = self.keyword_names().iter().map(|name| keyword_name_ident(name)).collect::<Vec<proc_macro2::Ident>>();
+            expr = syn::parse_quote! { #expr.and_then(|_| _arguments.unknown_keywords(&[#(&#known),*])) };
+        }
+        expr
+    }
+
     fn method_exception_block_from_arguments(&self) -> syn::Block {
         let mut block: syn::Block = syn::parse_quote! { {} };
+        block.stmts.push(syn::parse_quote! {
+            if let Err(e) = &self.argument_error {
+                return Some(e);
+            }
+        });
         for arg in self.arguments.iter() {
             let ident = &arg.name.ident;
             block.stmts.push(syn::parse_quote! {
@@ -147,33 +247,31 @@ Self {
         let mut call: syn::ExprCall = syn::parse_quote! { #fn_name() };
         for arg in self.arguments.iter() {
             let arg_name = &arg.name.ident;
-            let arg_name: syn::ExprMethodCall = syn::parse_quote! { self.#arg_name() };
+            let arg_name: syn::ExprMethodCall = syn::parse_quote! { self.#arg_name.into_value() };
             call.args.push(syn::Expr::from(arg_name));
         }
         call
     }
 
-    fn method_argument_methods(&self) -> proc_macro2::TokenStream {
-        let mut content = quote::quote! {};
-        for arg in self.arguments.iter() {
-            let ident = &arg.name.ident;
-            let ty = &arg.ty;
-            content = quote::quote! {
-                fn #ident(&self) -> #ty {
-                    self.#ident.result.as_ref().ok().unwrap().value().clone().into()
-                }
-            };
-        }
-        content
-    }
-
     fn method_fn_call(&self, class_name: &proc_macro2::Ident, rutie_class: &proc_macro2::Ident) -> proc_macro2::TokenStream {
         let fn_call = self.method_fn_call_expr();
-        let content = if self.kind == MethodKind::Instance {
+        let content = if self.uses_fields_struct() {
+            // 使うフィールドが分かるときは、そのフィールドだけ変換する
+            let struct_name = self.fields_struct_name(rutie_class);
+            let fields = self.self_fields.iter().flatten().map(|(name, _)| {
+                let reader = field_reader_ident(name);
+                quote::quote! { #name: self.rtself.#reader()? }
+            });
+            let mutability = if self.takes_mut_self() { Some(quote::quote! { mut }) } else { None };
             quote::quote! {
-                let _self = #class_name::try_from(#rutie_class { value: self.rtself.value() });
+                let #mutability _self = #struct_name { #(#fields),* };
+                let result = _self.#fn_call;
+            }
+        } else if self.kind == MethodKind::Instance {
+            quote::quote! {
+                let _self = <#class_name as std::convert::TryFrom<#rutie_class>>::try_from(#rutie_class { value: self.rtself.value() });
                 if let Err(e) = _self {
-                    return e.to_any_object();
+                    return Err(e.into());
                 }
                 let result = _self.unwrap().#fn_call;
             }
@@ -183,9 +281,27 @@ Self {
             }
         };
 
+        // rbclassの構造体を返すときはRubyのインスタンスを作る
+        // Result<T, E> のErrは呼び出し元でRustの値をすべてdropしてからraiseするか、ブロックからのbreakを続ける
+        let result = if self.returns_result() {
+            quote::quote! { result.map(rutie_attr_backend::IntoRuby::into_ruby).map_err(std::convert::Into::into) }
+        } else {
+            quote::quote! { Ok(rutie_attr_backend::IntoRuby::into_ruby(result)) }
+        };
         quote::quote! {
             #content
-            result.to_any_object()
+            #result
+        }
+    }
+
+    // Result<T, E> を返す関数
+    fn returns_result(&self) -> bool {
+        match &self.return_type {
+            syn::ReturnType::Type(_, ty) => match &**ty {
+                syn::Type::Path(p) => p.path.segments.last().is_some_and(|segment| segment.ident == "Result"),
+                _ => false,
+            },
+            syn::ReturnType::Default => false,
         }
     }
 
@@ -194,21 +310,46 @@ Self {
         let fn_call = self.method_fn_call(class_name, rutie_class);
         let expr_struct = self.method_struct_impl_expr_struct();
         let exception_block = self.method_exception_block_from_arguments();
-        let methods = self.method_argument_methods();
+        let (required, optional, post, keywords) = self.positional_counts();
+        // キーワード引数のSymbolは最初の呼び出しで一度だけinternする
+        let keyword_statics = self.keyword_names().into_iter().map(|name| {
+            let ident = keyword_name_ident(&name);
+            quote::quote! { static #ident: rutie_attr_backend::KeywordName = rutie_attr_backend::KeywordName::new(#name); }
+        });
+        // エラーメッセージに使うRubyでのメソッド名
+        let method_name = self.method_label(class_name);
+        // デフォルト値の定数はメソッドを定義したクラスから探す
+        let class_name_const = if self.arguments.iter().filter_map(|arg| arg.default_value.as_ref()).any(|v| v.has_constant()) {
+            let class_name = class_name.to_string();
+            Some(quote::quote! { const CLASS_NAME: &str = #class_name; })
+        } else {
+            None
+        };
+        // rust!(expr) のデフォルト値の型のチェック
+        let default_value_assertions = self.arguments.iter().flat_map(|arg| arg.default_value_assertions());
         quote::quote! {
             impl #struct_name {
-                pub fn new(argc: rutie::types::Argc, argv: *const rutie::AnyObject, rtself: #rutie_class, default_value_map: &std::collections::HashMap<&str, rutie::AnyObject>) -> Self {
-                    let _arguments = rutie::util::parse_arguments(argc, argv);
+                pub fn new(argc: rutie::types::Argc, argv: *const rutie::AnyObject, rtself: #rutie_class) -> Self {
+                    #(#keyword_statics)*
+                    #(#default_value_assertions)*
+                    const METHOD_NAME: &str = #method_name;
+                    #class_name_const
+                    // argc, argvはRubyのメソッド呼び出しから渡されたもの
+                    let _arguments = rutie_attr_backend::ArgumentList::new(
+                        unsafe { rutie_attr_backend::arguments(argc, argv) },
+                        #required,
+                        #optional,
+                        #post,
+                        #keywords,
+                    );
                     #expr_struct
                 }
 
                 fn exception(&self) -> Option<&rutie::AnyException> #exception_block
 
-                #methods
-
-                pub fn invoke(&self) -> rutie::AnyObject {
+                pub fn invoke(self) -> Result<rutie::AnyObject, rutie_attr_backend::BlockError> {
                     if let Some(e) = self.exception() {
-                        return e.to_any_object();
+                        return Err(rutie::AnyException::from(e.value()).into());
                     }
                     #fn_call
                 }
@@ -218,40 +359,136 @@ Self {
 
     pub fn fn_call(&self, class_name: &proc_macro2::Ident) -> proc_macro2::TokenStream {
         let struct_name = self.method_struct_name(class_name);
-        let mut content = quote::quote! {
-            let mut default_value_map = std::collections::HashMap::new();
-        };
-        for arg in self.arguments.iter() {
-            let arg_name = &arg.name.ident.to_string();
-            if let Some(default_value) = &arg.default_value {
-                let default_value = default_value.to_default_value(&arg.ty);
-                content = quote::quote! {
-                    #content
-                    default_value_map.insert(#arg_name, #default_value.to_any_object());
-                };
-            }
-        }
+        // 例外やブロックからのbreakは、Rustの値をすべてdropしてからraiseしたり続けたりする
         quote::quote! {
-            #content
-            #struct_name::new(argc, argv, _rtself, &default_value_map).invoke()
+            let result = #struct_name::new(argc, argv, _rtself).invoke();
+            match result {
+                Ok(o) => o,
+                Err(e) => {
+                    e.resume();
+                    rutie::NilClass::new().to_any_object()
+                },
+            }
         }
     }
 
-    fn arguments_order(&self) -> HashMap<syn::PatIdent, usize> {
-        let mut i = 0;
+    // 省略可能な引数か可変長引数より後ろの必須引数は、Rubyと同じく後置の引数として末尾から取る
+    fn arguments_order(&self) -> HashMap<syn::PatIdent, ArgumentPosition> {
+        let (mut required, mut optional, mut post) = (0, 0, 0);
+        let mut after_optional = false;
         let mut h = HashMap::new();
-        for arg in self.arguments.iter() {
-            h.insert(arg.name.clone(), i);
-            if let ArgumentKind::KwArg = arg.kind {
-            } else {
-                i += 1;
-            }
+        // Rubyのシグネチャの順番で並べる。シグネチャに無い引数はRbdefで先頭に足してある
+        let mut arguments = self.arguments.iter().collect::<Vec<&Argument>>();
+        arguments.sort_by_key(|arg| self.def_signature.position(&arg.name.ident.to_string()).unwrap_or(usize::MAX));
+        for arg in arguments {
+            let position = match arg.kind {
+                ArgumentKind::Arg if after_optional => {
+                    post += 1;
+                    ArgumentPosition::Post(post - 1)
+                },
+                ArgumentKind::Arg => {
+                    required += 1;
+                    ArgumentPosition::Required(required - 1)
+                },
+                ArgumentKind::DArg => {
+                    after_optional = true;
+                    optional += 1;
+                    ArgumentPosition::Optional(optional - 1)
+                },
+                ArgumentKind::Splat => {
+                    after_optional = true;
+                    ArgumentPosition::Splat
+                },
+                ArgumentKind::KwArg => ArgumentPosition::Keyword,
+                ArgumentKind::DoubleSplat => ArgumentPosition::DoubleSplat(self.keyword_names()),
+                ArgumentKind::Block => ArgumentPosition::Block,
+            };
+            h.insert(arg.name.clone(), position);
         }
         h
     }
 
-    pub fn def_name(&self) -> proc_macro2::Literal {
-        let s = if self.def_name.is_empty() {
+    fn keyword_names(&self) -> Vec<String> {
+        self.arguments
+            .iter()
+            .filter(|arg| matches!(arg.kind, ArgumentKind::KwArg))
+            .map(|arg| arg.name.ident.to_string())
+            .collect()
+    }
+
+    // キーワード引数があって **opts が無いときは、知らないキーワードをエラーにする
+    fn checks_unknown_keywords(&self) -> bool {
+        let has_double_splat = self.arguments.iter().any(|arg| matches!(arg.kind, ArgumentKind::DoubleSplat));
+        !has_double_splat && !self.keyword_names().is_empty()
+    }
+
+    // 省略できないキーワード引数
+    fn required_keyword_names(&self) -> Vec<String> {
+        self.arguments
+            .iter()
+            .filter(|arg| matches!(arg.kind, ArgumentKind::KwArg) && !arg.is_optional())
+            .map(|arg| arg.name.ident.to_string())
+            .collect()
+    }
+
+    // 位置引数の個数の(下限, 上限)。*rest があるときは上限なし
+    // 末尾に並んだOption<T>の必須引数は省略できるので下限に含めない
+    // 途中のOption<T>は後ろの引数がずれないように省略できず、nilだけを受け付ける
+    fn arity_range(&self) -> (usize, Option<usize>) {
+        let (required, optional, post, _) = self.positional_counts();
+        let orders = self.arguments_order();
+        let mut positional = self.arguments
+            .iter()
+            .filter_map(|arg| match orders[&arg.name] {
+                ArgumentPosition::Required(i) => Some(((0, i), arg)),
+                ArgumentPosition::Post(i) => Some(((1, i), arg)),
+                _ => None,
+            })
+            .collect::<Vec<((usize, usize), &Argument)>>();
+        positional.sort_by_key(|(order, _)| *order);
+        let omittable = positional
+            .iter()
+            .rev()
+            .take_while(|(_, arg)| arg.is_optional())
+            .count();
+        let has_splat = self.arguments.iter().any(|arg| matches!(arg.kind, ArgumentKind::Splat));
+        let minimum = required + post - omittable;
+        let maximum = if has_splat { None } else { Some(required + optional + post) };
+        (minimum, maximum)
+    }
+
+    // 省略できる部分の無いメソッドは、Rubyに固定の引数の個数で登録する
+    // rb_define_methodで指定できるのは15個まで
+    pub fn fixed_arity(&self) -> Option<usize> {
+        let is_fixed = self.arguments.iter().all(|arg| {
+            matches!(arg.kind, ArgumentKind::Block) || (matches!(arg.kind, ArgumentKind::Arg) && !arg.is_optional())
+        });
+        let (minimum, _) = self.arity_range();
+        if is_fixed && minimum <= 15 {
+            Some(minimum)
+        } else {
+            None
+        }
+    }
+
+    // (必須, 省略可能, 後置, キーワード引数の有無)
+    fn positional_counts(&self) -> (usize, usize, usize, bool) {
+        self.arguments_order()
+            .values()
+            .fold((0, 0, 0, false), |(required, optional, post, keywords), position| {
+                match position {
+                    ArgumentPosition::Required(_) => (required + 1, optional, post, keywords),
+                    ArgumentPosition::Optional(_) => (required, optional + 1, post, keywords),
+                    ArgumentPosition::Post(_) => (required, optional, post + 1, keywords),
+                    ArgumentPosition::Splat | ArgumentPosition::Block => (required, optional, post, keywords),
+                    ArgumentPosition::Keyword | ArgumentPosition::DoubleSplat(_) => (required, optional, post, true),
+                }
+            })
+    }
+
+    // Rubyでのメソッド名
+    pub fn ruby_name(&self) -> String {
+        if self.def_name.is_empty() {
             self.fn_name.to_string()
         } else {
             self.def_name
@@ -267,43 +504,97 @@ Self {
                             acc.push_str(&punct.as_char().to_string());
                             acc
                         },
+                        // [] と []= の []
+                        proc_macro2::TokenTree::Group(group) if group.delimiter() == proc_macro2::Delimiter::Bracket => {
+                            acc.push_str("[]");
+                            acc
+                        },
+                        // name = "..." で書いた名前
+                        proc_macro2::TokenTree::Literal(literal) => {
+                            let name = syn::parse_str::<syn::LitStr>(&literal.to_string()).map(|s| s.value()).unwrap_or_default();
+                            acc.push_str(&name);
+                            acc
+                        },
                         _ => unreachable!("exists other TokenTree at def_name"),
                     }
                 })
+        }
+    }
+
+    // Foo#bar または Foo.bar
+    fn method_label(&self, class_name: &proc_macro2::Ident) -> String {
+        let separator = match self.kind {
+            MethodKind::Instance => "#",
+            MethodKind::Static => ".",
         };
-        proc_macro2::Literal::string(&s)
+        format!("{}{}{}", class_name, separator, self.ruby_name())
     }
 
-    fn validate_def_name(&self) -> syn::Result<()> {
-        let mut names = self.def_name.clone().into_iter();
+    // 別名を含めた、Rubyで登録するすべての名前
+    pub fn ruby_names(&self) -> Vec<String> {
+        self.spanned_ruby_names().into_iter().map(|(name, _)| name).collect()
+    }
 
-        let message = "The def_name must be an alphabetic or underscore ident and end with ! or ? symbol at the end.";
+    // Rubyで登録する名前と、エラーのspan用にその名前を書いた場所
+    pub fn spanned_ruby_names(&self) -> Vec<(String, proc_macro2::TokenStream)> {
+        let mut names = vec![(self.ruby_name(), self.def_name_tokens())];
+        names.extend(self.aliases.iter().map(|alias| (alias.value(), quote::quote! { #alias })));
+        names
+    }
 
-        // def_nameの最初のTokenTreeはIdentであること
-        let first = names.next();
-        if let Some(proc_macro2::TokenTree::Ident(_)) = first {
-            // noop
+    // rbdef attributeで名前を省略したときは関数名
+    fn def_name_tokens(&self) -> proc_macro2::TokenStream {
+        if self.def_name.is_empty() {
+            let fn_name = &self.fn_name;
+            quote::quote! { #fn_name }
         } else {
-            return Err(syn::Error::new_spanned(first, message));
+            self.def_name.clone()
         }
+    }
 
-        // def_nameの２つめのTokenTreeが存在する場合は、!か?であること。
-        let second = names.next();
-        if let Some(proc_macro2::TokenTree::Punct(p)) = second {
-            let c = p.as_char();
-            if c != '!' && c != '?' {
-                return Err(syn::Error::new_spanned(p, "The end of the def_name is ! or ? punctuations can be specified."));
-            }
-        } else {
-            return Err(syn::Error::new_spanned(second, message));
+    // メソッドが登録される名前空間。module_functionは特異メソッドとインスタンスメソッドの両方
+    pub fn namespaces(&self) -> Vec<MethodKind> {
+        match self.visibility {
+            Visibility::ModuleFunction => vec![MethodKind::Static, MethodKind::Instance],
+            _ => vec![self.kind.clone()],
         }
+    }
+
+    // 別名も本来の名前と同じ規則でチェックする
+    fn validate_aliases(&self) -> syn::Result<()> {
+        let (minimum, maximum) = self.arity_range();
+        let errors = self.aliases
+            .iter()
+            .filter_map(|alias| {
+                let name = alias.value();
+                let result = match MethodNameKind::classify(&name) {
+                    Some(kind) => kind.check_arity(&name, minimum, maximum),
+                    None => Err(format!("`{}` is not a valid Ruby method name.", name)),
+                };
+                result.err().map(|message| syn::Error::new_spanned(alias, message))
+            })
+            .collect::<Vec<syn::Error>>();
 
-        if let Some(third) = names.next() {
-            // 3つ目はありえない
-            return Err(syn::Error::new_spanned(third, message));
+        if let Some(e) = combined_errors(errors) {
+            Err(e)
+        } else {
+            Ok(())
         }
+    }
 
-        Ok(())
+    fn validate_def_name(&self) -> syn::Result<()> {
+        let name = self.ruby_name();
+        let spanned = |message: String| syn::Error::new_spanned(self.def_name_tokens(), message);
+
+        // Rubyのメソッド名として使える名前であること
+        let kind = match MethodNameKind::classify(&name) {
+            Some(kind) => kind,
+            None => return Err(spanned(format!("`{}` is not a valid Ruby method name.", name))),
+        };
+
+        // 演算子やセッターは呼び出し方で引数の個数が決まる
+        let (minimum, maximum) = self.arity_range();
+        kind.check_arity(&name, minimum, maximum).map_err(spanned)
     }
 
     fn validate_def_signature(&self) -> syn::Result<()> {
@@ -313,35 +604,56 @@ Self {
             .collect::<Vec<String>>();
 
         // rbdef attributeで定義している引数名が、実装の引数に存在しないときはエラー
-        let errors = self.def_signature_map
-            .keys()
-            .filter_map(|ident| {
-                if !arg_names.contains(&ident) {
-                    let t = proc_macro2::TokenStream::from_iter(self.def_signature_map[ident].clone());
-                    Some(syn::Error::new_spanned(t, "not found in arguments."))
-                } else {
-                    None
-                }
-            })
+        let mut errors = self.def_signature
+            .parameters
+            .iter()
+            .filter(|parameter| !arg_names.contains(&parameter.name.to_string()))
+            .map(|parameter| syn::Error::new_spanned(&parameter.tokens, "not found in arguments."))
             .collect::<Vec<syn::Error>>();
 
+        // 引数の並び順と重複
+        if let Err(e) = self.def_signature.validate() {
+            errors.push(e);
+        }
+
+        // デフォルト値が引数の型に変換できないときはエラー
+        for arg in self.arguments.iter() {
+            if let Err(message) = arg.check_default_value() {
+                let name = arg.name.ident.to_string();
+                let message = format!("default value of `{}` does not match the type: {}.", name, message);
+                match self.def_signature.get(&name).and_then(|p| p.default_value.as_ref()) {
+                    Some(tokens) => errors.push(syn::Error::new_spanned(tokens, message)),
+                    None => errors.push(syn::Error::new_spanned(&arg.ty, message)),
+                }
+            }
+        }
+
         if let Some(e) = combined_errors(errors) {
             Err(e)
         } else {
             Ok(())
         }
+    }
 
-        // TODO: default_valueの値がty型にコンバートできなかったら、コンパイルエラーにする
-        // コンパイル時に評価するいい方法が思いつかないので、一旦実行時にRuntimeErrorにする
+    fn validate_visibility(&self) -> syn::Result<()> {
+        // module_functionの特異メソッドはselfがクラスになるので、&selfを受け取るメソッドには使えない
+        if self.visibility == Visibility::ModuleFunction && self.kind == MethodKind::Instance {
+            let message = "visibility = module_function can only be used with a method without self.";
+            return Err(syn::Error::new_spanned(&self.fn_name, message));
+        }
+        Ok(())
     }
 
     pub fn validate(&self) -> syn::Result<()> {
         let mut errors = [
             self.validate_def_name(),
             self.validate_def_signature(),
+            self.validate_visibility(),
+            self.validate_aliases(),
         ].iter()
             .filter_map(|e| e.clone().err())
             .collect::<Vec<syn::Error>>();
+        errors.extend(self.attribute_errors.iter().cloned());
 
         let arguments_errors = self.arguments
             .iter()
@@ -0,0 +1,67 @@
+// Rubyのメソッド名の種類
+#[derive(Debug, PartialEq)]
+pub enum MethodNameKind {
+    // foo, foo?, foo!
+    Identifier,
+    // foo=
+    Setter,
+    // +@ -@ ! ~
+    UnaryOperator,
+    // + - * / == <=> など
+    BinaryOperator,
+    // []
+    Index,
+    // []=
+    IndexSet,
+}
+
+const UNARY_OPERATORS: &[&str] = &["+@", "-@", "!", "~"];
+
+const BINARY_OPERATORS: &[&str] = &[
+    "+", "-", "*", "/", "%", "**", "==", "!=", "===", "=~", "!~", "<", ">", "<=", ">=", "<=>", "<<", ">>", "&", "|",
+    "^", "`",
+];
+
+impl MethodNameKind {
+    // Rubyでメソッド名として使えないときはNone
+    pub fn classify(name: &str) -> Option<MethodNameKind> {
+        match name {
+            "[]" => return Some(Self::Index),
+            "[]=" => return Some(Self::IndexSet),
+            _ => {},
+        }
+        if UNARY_OPERATORS.contains(&name) {
+            return Some(Self::UnaryOperator);
+        }
+        if BINARY_OPERATORS.contains(&name) {
+            return Some(Self::BinaryOperator);
+        }
+
+        let (body, kind) = match name.chars().last() {
+            Some('?') | Some('!') => (&name[..name.len() - 1], Self::Identifier),
+            Some('=') => (&name[..name.len() - 1], Self::Setter),
+            _ => (name, Self::Identifier),
+        };
+        let mut chars = body.chars();
+        let first = chars.next()?;
+        if (first.is_alphabetic() || first == '_') && chars.all(|c| c.is_alphanumeric() || c == '_') {
+            Some(kind)
+        } else {
+            None
+        }
+    }
+
+    // 呼び出し方から決まる位置引数の個数のチェック
+    // 引数の個数の範囲 (minimum, maximum) で呼び出せないときはエラーメッセージを返す
+    pub fn check_arity(&self, name: &str, minimum: usize, maximum: Option<usize>) -> Result<(), String> {
+        let accepts = |n: usize| minimum <= n && maximum.is_none_or(|maximum| n <= maximum);
+        match self {
+            Self::Identifier | Self::Index => Ok(()),
+            Self::Setter if !accepts(1) => Err(format!("setter method `{}` must accept exactly one argument.", name)),
+            Self::BinaryOperator if !accepts(1) => Err(format!("binary operator `{}` must accept exactly one argument.", name)),
+            Self::UnaryOperator if !accepts(0) => Err(format!("unary operator `{}` must accept no arguments.", name)),
+            Self::IndexSet if maximum == Some(0) => Err(format!("`{}` must accept at least one argument for the value.", name)),
+            _ => Ok(()),
+        }
+    }
+}
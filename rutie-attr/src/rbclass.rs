@@ -1,6 +1,7 @@
+use super::util::field_reader_ident;
 use super::DEFINED_CLASSES;
 use proc_macro::TokenStream;
-use quote::quote;
+use quote::{quote, ToTokens};
 
 pub struct Rbclass {
     item: syn::ItemStruct,
@@ -17,30 +18,54 @@ impl Rbclass {
         let class_name = class.to_string();
         let rutie_class = quote::format_ident!("Rutie{}", class);
 
+        // rbmethodsで使うフィールドの型も登録する
         DEFINED_CLASSES
             .lock()
             .unwrap()
-            .entry(class_name)
-            .or_insert_with(|| self.field_names());
+            .insert(class_name, self.field_names_and_types());
 
-        let content = self.impl_try_from(class);
+        let field_readers = self.impl_field_readers();
+        let class_struct = self.construct_class(class);
+        let into_ruby = self.impl_from_class(class);
+        let from_ruby = self.impl_from_ruby(class, &rutie_class);
 
         let gen = quote! {
             #ast
             rutie::class!(#rutie_class);
 
+            impl #rutie_class {
+                #field_readers
+            }
+
             impl std::convert::TryFrom<#rutie_class> for #class {
                 type Error = rutie::AnyException;
 
                 fn try_from(f: #rutie_class) -> Result<Self, Self::Error> {
-                    #content
+                    Ok(#class_struct)
+                }
+            }
+
+            // TryFromの逆で、構造体からRubyのインスタンスを作る
+            impl From<#class> for #rutie_class {
+                fn from(f: #class) -> Self {
+                    #into_ruby
                 }
             }
+
+            impl rutie_attr_backend::IntoRuby for #class {
+                fn into_ruby(self) -> rutie::AnyObject {
+                    rutie::Object::to_any_object(&#rutie_class::from(self))
+                }
+            }
+
+            #from_ruby
         };
         gen.into()
     }
 
-    fn impl_try_from(&self, class: &syn::Ident) -> proc_macro2::TokenStream {
+    // フィールドごとに、インスタンス変数を読んでnilと型をチェックする関数
+    // インスタンスメソッドは self.field で読むフィールドの関数だけを呼ぶ
+    fn impl_field_readers(&self) -> proc_macro2::TokenStream {
         let mut content = quote! {};
         if let syn::Fields::Named(fields) = &self.item.fields {
             for n in fields.named.iter() {
@@ -48,30 +73,66 @@ impl Rbclass {
                     let value = ident.to_string();
                     let nil_error_message = format!("{} field is nil.", value);
                     let ty = &n.ty;
+                    let reader = field_reader_ident(ident);
+                    // readerをsendせずに、キャッシュしたIDでインスタンス変数を直接読む
+                    let ivar = quote::format_ident!("IVAR_{}", value.to_uppercase());
+                    let ivar_name = format!("@{}", value);
                     content = quote! {
                         #content
 
-                        let #ident = unsafe { f.send(#value, &[]) };
-                        if let Ok(_) = #ident.try_convert_to::<rutie::NilClass>() {
-                            return Err(rutie::AnyException::new("StandardError", Some(#nil_error_message)));
+                        #[allow(dead_code)]
+                        fn #reader(&self) -> Result<#ty, rutie::AnyException> {
+                            static #ivar: rutie_attr_backend::InstanceVariable = rutie_attr_backend::InstanceVariable::new(#ivar_name);
+                            let value = #ivar.get(self);
+                            if rutie::Object::is_nil(&value) {
+                                return Err(rutie::AnyException::new("StandardError", Some(#nil_error_message)));
+                            }
+                            rutie::Object::try_convert_to::<#ty>(&value)
                         }
-
-                        let #ident = #ident.try_convert_to::<#ty>();
-                        if let Err(e) = #ident {
-                            return Err(e);
-                        }
-                        let #ident = #ident.unwrap();
                     };
                 }
             }
         }
+        content
+    }
 
-        let class_struct = self.construct_class(&class);
-        content = quote! {
-            #content
-            Ok(#class_struct)
+    // initializeを呼ばずにallocateして、フィールドをインスタンス変数に直接書く
+    fn impl_from_class(&self, class: &syn::Ident) -> proc_macro2::TokenStream {
+        let class_name = class.to_string();
+        let mut content = quote! {
+            let object = rutie_attr_backend::allocate(#class_name);
         };
-        content
+        if let syn::Fields::Named(fields) = &self.item.fields {
+            for n in fields.named.iter() {
+                if let Some(ident) = &n.ident {
+                    let value = ident.to_string();
+                    let ivar = quote::format_ident!("IVAR_{}", value.to_uppercase());
+                    let ivar_name = format!("@{}", value);
+                    content = quote! {
+                        #content
+
+                        static #ivar: rutie_attr_backend::InstanceVariable = rutie_attr_backend::InstanceVariable::new(#ivar_name);
+                        #ivar.set(&object, rutie_attr_backend::IntoRuby::into_ruby(f.#ident));
+                    };
+                }
+            }
+        }
+        quote! {
+            #content
+            Self::from(rutie::Object::value(&object))
+        }
+    }
+
+    // rbdefの引数で構造体を受け取れるようにする
+    fn impl_from_ruby(&self, class: &syn::Ident, rutie_class: &syn::Ident) -> proc_macro2::TokenStream {
+        let class_name = class.to_string();
+        quote! {
+            impl rutie_attr_backend::FromRuby for #class {
+                fn from_ruby(from: &rutie::AnyObject, argument: &rutie_attr_backend::ArgumentName) -> Result<Self, rutie::AnyException> {
+                    rutie_attr_backend::convert_class::<#class, #rutie_class>(from, #class_name, argument)
+                }
+            }
+        }
     }
 
     fn construct_class(&self, class: &syn::Ident) -> syn::ExprStruct {
@@ -79,7 +140,8 @@ impl Rbclass {
         if let syn::Fields::Named(fields) = &self.item.fields {
             for n in fields.named.iter() {
                 if let Some(ident) = &n.ident {
-                    let field: syn::FieldValue = syn::parse_quote! { #ident: #ident };
+                    let reader = field_reader_ident(ident);
+                    let field: syn::FieldValue = syn::parse_quote! { #ident: f.#reader()? };
                     cstruct.fields.push(field);
                 }
             }
@@ -87,12 +149,12 @@ impl Rbclass {
         cstruct
     }
 
-    fn field_names(&self) -> Vec<String> {
+    fn field_names_and_types(&self) -> Vec<(String, String)> {
         let mut names = Vec::new();
         if let syn::Fields::Named(fields) = &self.item.fields {
             for n in fields.named.iter() {
                 if let Some(ident) = &n.ident {
-                    names.push(ident.to_string());
+                    names.push((ident.to_string(), n.ty.to_token_stream().to_string()));
                 }
             }
         }
@@ -1,6 +1,7 @@
 use super::argument::{Argument, ArgumentKind, ArgumentDefaultValue};
-use super::method::{Method, MethodKind};
-use std::collections::HashMap;
+use super::method::{Method, MethodKind, Visibility};
+use super::signature::Signature;
+use quote::ToTokens;
 use std::iter::FromIterator;
 
 pub struct Rbdef {
@@ -13,22 +14,54 @@ impl Rbdef {
         Self { item, attr }
     }
 
-    pub fn method_info(&self) -> Method {
+    // class_fieldsはrbclassで登録した (フィールド名, 型のトークン列)
+    pub fn method_info(&self, class_fields: &[(String, String)]) -> Method {
         // attribute名が rbdef だったらrutie::methods!で定義して、externする
         let fn_name = self.parse_fn_name();
-        let (def_name, def_signature) = self.parse_attribute();
-        let def_signature_map = self.def_signature_map(def_signature);
+        let mut attribute_errors = Vec::new();
+        let (def_name, def_signature, options) = self.parse_attribute(&mut attribute_errors);
+        let mut def_signature = Signature::parse(def_signature);
+        self.validate_duplicate_options(&options, &mut attribute_errors);
+        let def_name = self.parse_name_option(def_name, &options, &mut attribute_errors);
+        let visibility = self.parse_visibility(&options, &mut attribute_errors);
+        let aliases = self.parse_aliases(&options, &mut attribute_errors);
+
+        let arguments = self.parse_arguments(&def_signature);
+        def_signature.prepend_required(arguments.iter().map(|arg| &arg.name.ident));
 
         Method {
             def_name,
             fn_name,
-            kind: self.parse_method_kind(),
+            kind: self.parse_method_kind(&options, &mut attribute_errors),
             return_type: self.parse_return_type(),
-            arguments: self.parse_arguments(&def_signature_map),
-            def_signature_map,
+            arguments,
+            def_signature,
+            visibility,
+            aliases,
+            self_fields: self.parse_self_fields(class_fields),
+            item: self.item.clone(),
+            attribute_errors,
         }
     }
 
+    // メソッドの本体で self.field の形で読んでいるフィールドとその型
+    // Self を使うメソッドや、クラスに無いフィールドを読むメソッドはNone
+    fn parse_self_fields(&self, class_fields: &[(String, String)]) -> Option<Vec<(proc_macro2::Ident, syn::Type)>> {
+        if uses_self_type(self.item.sig.to_token_stream()) || uses_self_type(self.item.block.to_token_stream()) {
+            return None;
+        }
+        let mut fields = Vec::new();
+        collect_self_fields(self.item.block.to_token_stream(), &mut fields)?;
+        fields
+            .iter()
+            .map(|field| {
+                let (name, ty) = class_fields.iter().find(|(name, _)| name == field)?;
+                let ty = syn::parse_str::<syn::Type>(ty).ok()?;
+                Some((quote::format_ident!("{}", name), ty))
+            })
+            .collect()
+    }
+
     // impl内に定義されているメソッド名を取得する
     fn parse_fn_name(&self) -> proc_macro2::Ident {
         self.item.sig.ident.clone()
@@ -40,25 +73,55 @@ impl Rbdef {
     }
 
     // 関数のattributeからRuby側の関数名と関数のデフォルト引数をTokenStreamで取得する
-    fn parse_attribute(&self) -> (proc_macro2::TokenStream, proc_macro2::Group) {
+    // visibility = private のような key = value のオプションは別に返す
+    // メソッド名とシグネチャは1つだけで、知らないオプションはエラーにする
+    fn parse_attribute(&self, errors: &mut Vec<syn::Error>) -> (proc_macro2::TokenStream, proc_macro2::Group, Vec<RbdefOption>) {
         let empty_token_stream = proc_macro2::TokenStream::new();
         let empty_def_signature = proc_macro2::Group::new(
             proc_macro2::Delimiter::Parenthesis,
             empty_token_stream.clone(),
         );
         if self.attr.tokens.is_empty() {
-            return (empty_token_stream, empty_def_signature);
+            return (empty_token_stream, empty_def_signature, Vec::new());
         }
 
         if let proc_macro2::TokenTree::Group(outer_group) =
             self.attr.tokens.clone().into_iter().next().unwrap()
         {
+            let trees = outer_group.stream().into_iter().collect::<Vec<proc_macro2::TokenTree>>();
+            let segments = trees
+                .split(|tree| matches!(tree, proc_macro2::TokenTree::Punct(p) if p.as_char() == ','))
+                .filter(|segment| !segment.is_empty());
+            let mut options = Vec::new();
+            let mut def: Option<&[proc_macro2::TokenTree]> = None;
+            for segment in segments {
+                if RbdefOption::is_option(segment) {
+                    options.push(RbdefOption::from(segment));
+                } else if let Some(key) = RbdefOption::unknown_key(segment) {
+                    let message = format!("unknown rbdef option `{}`. Expected one of {}.", key, RbdefOption::KEYS.join(", "));
+                    errors.push(syn::Error::new_spanned(key, message));
+                } else if def.is_none() {
+                    def = Some(segment);
+                } else if let [proc_macro2::TokenTree::Ident(flag)] = segment {
+                    let message = format!("unknown rbdef flag `{}`. Expected one of {}.", flag, RbdefOption::FLAGS.join(", "));
+                    errors.push(syn::Error::new_spanned(flag, message));
+                } else {
+                    let tokens = proc_macro2::TokenStream::from_iter(segment.iter().cloned());
+                    errors.push(syn::Error::new_spanned(tokens, "rbdef takes only one method name and signature."));
+                }
+            }
+
             let (def_signature, def_name): (
                 Vec<proc_macro2::TokenTree>,
                 Vec<proc_macro2::TokenTree>,
-            ) = outer_group.stream().into_iter().partition(|tree| {
-                matches!(tree, proc_macro2::TokenTree::Group(_))
+            ) = def.unwrap_or_default().iter().cloned().partition(|tree| {
+                // [] と []= の [] はメソッド名の一部
+                matches!(tree, proc_macro2::TokenTree::Group(g) if g.delimiter() == proc_macro2::Delimiter::Parenthesis)
             });
+            // def name(a, b = 1) のように先頭に def を書いてもよい
+            let def_name = def_name
+                .into_iter()
+                .skip_while(|tree| matches!(tree, proc_macro2::TokenTree::Ident(ident) if ident == "def"));
             let def_name = proc_macro2::TokenStream::from_iter(def_name);
             let def_signature = def_signature.into_iter().find_map(|tree| {
                 if let proc_macro2::TokenTree::Group(g) = tree {
@@ -70,29 +133,99 @@ impl Rbdef {
             (
                 def_name,
                 def_signature.unwrap_or(empty_def_signature),
+                options,
             )
         } else {
             unreachable!("exists other TokenTree at rbdef attribute");
         }
     }
 
-    fn parse_method_kind(&self) -> MethodKind {
-        self.item
-            .sig
-            .inputs
+    // name = "..." で名前を文字列で書いたときは、引数は args(...) に書く
+    // #[rbdef(name = "[]=", args(index, value))]
+    fn parse_name_option(&self, def_name: proc_macro2::TokenStream, options: &[RbdefOption], errors: &mut Vec<syn::Error>) -> proc_macro2::TokenStream {
+        let option = match options.iter().find(|option| option.key == "name") {
+            Some(option) => option,
+            None => return def_name,
+        };
+        if !def_name.is_empty() && def_name.to_string() != "args" {
+            errors.push(syn::Error::new_spanned(&def_name, "write the arguments as args(...) when the name option is given."));
+        }
+        match syn::parse2::<syn::LitStr>(option.value.clone()) {
+            Ok(_) => option.value.clone(),
+            Err(_) => {
+                errors.push(syn::Error::new_spanned(&option.value, "name must be a string literal."));
+                def_name
+            },
+        }
+    }
+
+    // alias = "length" 以外のオプションは1回だけ書ける。2回目をエラーにする
+    fn validate_duplicate_options(&self, options: &[RbdefOption], errors: &mut Vec<syn::Error>) {
+        for (i, option) in options.iter().enumerate() {
+            if option.key != "alias" && options[..i].iter().any(|o| o.key == option.key) {
+                errors.push(syn::Error::new_spanned(&option.key, format!("duplicate rbdef option `{}`.", option.key)));
+            }
+        }
+    }
+
+    // alias = "length" は何度でも書ける
+    fn parse_aliases(&self, options: &[RbdefOption], errors: &mut Vec<syn::Error>) -> Vec<syn::LitStr> {
+        options
             .iter()
-            .find_map(|input| {
-                if let syn::FnArg::Receiver(_) = input {
-                    Some(MethodKind::Instance)
-                } else {
+            .filter(|option| option.key == "alias")
+            .filter_map(|option| match syn::parse2::<syn::LitStr>(option.value.clone()) {
+                Ok(alias) => Some(alias),
+                Err(_) => {
+                    errors.push(syn::Error::new_spanned(&option.value, "alias must be a string literal."));
                     None
-                }
+                },
             })
-            .unwrap_or(MethodKind::Static)
+            .collect()
+    }
+
+    // visibility = public | private | protected | module_function
+    fn parse_visibility(&self, options: &[RbdefOption], errors: &mut Vec<syn::Error>) -> Visibility {
+        let option = match options.iter().find(|option| option.key == "visibility") {
+            Some(option) => option,
+            None => return Visibility::Public,
+        };
+        match option.value.to_string().as_str() {
+            "public" => Visibility::Public,
+            "private" => Visibility::Private,
+            "protected" => Visibility::Protected,
+            "module_function" => Visibility::ModuleFunction,
+            _ => {
+                let message = "visibility must be one of public, private, protected or module_function.";
+                errors.push(syn::Error::new_spanned(&option.value, message));
+                Visibility::Public
+            },
+        }
+    }
+
+    // #[rbdefself] か #[rbdef(singleton)] のときは特異メソッドにする
+    // それ以外はselfを受け取るかどうかで決める
+    fn parse_method_kind(&self, options: &[RbdefOption], errors: &mut Vec<syn::Error>) -> MethodKind {
+        let receiver = self.item.sig.inputs.iter().find_map(|input| {
+            if let syn::FnArg::Receiver(receiver) = input {
+                Some(receiver)
+            } else {
+                None
+            }
+        });
+        let singleton = self.attr.path.is_ident("rbdefself") || options.iter().any(|option| option.key == "singleton");
+
+        match receiver {
+            Some(receiver) if singleton => {
+                errors.push(syn::Error::new_spanned(receiver, "singleton method cannot take self."));
+                MethodKind::Static
+            },
+            Some(_) => MethodKind::Instance,
+            None => MethodKind::Static,
+        }
     }
 
     // メソッドの引数をいい感じにする
-    fn parse_arguments(&self, def_signature_map: &HashMap<String, Vec<proc_macro2::TokenTree>>) -> Vec<Argument> {
+    fn parse_arguments(&self, def_signature: &Signature) -> Vec<Argument> {
         self.item
             .sig
             .inputs
@@ -108,14 +241,14 @@ impl Rbdef {
             })
             .filter_map(|pat_type| {
                 if let syn::Pat::Ident(pat_ident) = *pat_type.pat.clone() {
-                    let (kind, default_value) = self.arg_type_and_default_value(def_signature_map, &pat_ident.ident);
                     //let ty = ArgumentType::from(*pat_type.ty.clone());
                     let ty = *pat_type.ty.clone();
+                    let (kind, default_value) = self.arg_type_and_default_value(def_signature, &pat_ident.ident);
                     Some(Argument {
                         name: pat_ident,
                         ty,
                         kind,
-                        default_value: default_value.map(ArgumentDefaultValue::from),
+                        default_value,
                     })
                 } else {
                     unreachable!("exists other PatType of PatIdent")
@@ -124,55 +257,167 @@ impl Rbdef {
             .collect()
     }
 
-    fn arg_type_and_default_value(&self, def_signature_map: &HashMap<String, Vec<proc_macro2::TokenTree>>, ident: &proc_macro2::Ident) -> (ArgumentKind, Option<ArgumentDefaultValue>) {
-        if let Some(tokens) = def_signature_map.get(&ident.to_string()) {
-            if tokens.is_empty() {
-                return (ArgumentKind::Arg, None);
-            }
-            let mut tokens = tokens.clone();
-            if let proc_macro2::TokenTree::Punct(p) = tokens.remove(0) {
-                let c = p.as_char();
-                let t = proc_macro2::TokenStream::from_iter(tokens.to_vec());
-                let default_value = Some(ArgumentDefaultValue::from(t));
-                if c == '=' {
-                    return (ArgumentKind::DArg, default_value);
-                } else if c == ':' {
-                    return (ArgumentKind::KwArg, default_value);
-                }
-            }
+    fn arg_type_and_default_value(&self, def_signature: &Signature, ident: &proc_macro2::Ident) -> (ArgumentKind, Option<ArgumentDefaultValue>) {
+        if let Some(parameter) = def_signature.get(&ident.to_string()) {
+            let default_value = parameter.default_value.clone().map(ArgumentDefaultValue::from);
+            (parameter.kind.clone(), default_value)
+        } else {
+            // rbdefのシグネチャに書かれていない引数は先頭の必須の引数にする(Option<T>はnilを受け付け、末尾なら省略可能)
+            (ArgumentKind::Arg, None)
+        }
+    }
+}
+
+// Self は別の構造体の上でメソッドを呼ぶと意味が変わる
+fn uses_self_type(tokens: proc_macro2::TokenStream) -> bool {
+    tokens.into_iter().any(|tree| match tree {
+        proc_macro2::TokenTree::Group(g) => uses_self_type(g.stream()),
+        proc_macro2::TokenTree::Ident(ident) => ident == "Self",
+        _ => false,
+    })
+}
+
+// self.field 以外の self の使い方 (メソッド呼び出しや self を渡すなど) があればNone
+fn collect_self_fields(tokens: proc_macro2::TokenStream, fields: &mut Vec<String>) -> Option<()> {
+    use proc_macro2::TokenTree::{Group, Ident, Punct};
+
+    let trees = tokens.into_iter().collect::<Vec<proc_macro2::TokenTree>>();
+    for (i, tree) in trees.iter().enumerate() {
+        match tree {
+            Group(g) => collect_self_fields(g.stream(), fields)?,
+            Ident(ident) if ident == "self" => match (trees.get(i + 1), trees.get(i + 2), trees.get(i + 3)) {
+                (Some(Punct(dot)), Some(Ident(field)), next) if dot.as_char() == '.' => {
+                    // self.method() と self.method::<T>() はメソッド呼び出し
+                    let is_method_call = match next {
+                        Some(Group(g)) => g.delimiter() == proc_macro2::Delimiter::Parenthesis,
+                        Some(Punct(p)) => p.as_char() == ':',
+                        _ => false,
+                    };
+                    if is_method_call {
+                        return None;
+                    }
+                    let field = field.to_string();
+                    if !fields.contains(&field) {
+                        fields.push(field);
+                    }
+                },
+                _ => return None,
+            },
+            _ => {},
         }
-        unreachable!("def_signature_map has not ident.")
-    }
-
-    fn def_signature_map(
-        &self,
-        group: proc_macro2::Group,
-    ) -> HashMap<String, Vec<proc_macro2::TokenTree>> {
-        group
-            .stream()
-            .into_iter()
-            .collect::<Vec<proc_macro2::TokenTree>>()
-            .split(Self::is_comma)
-            .into_iter()
-            .filter_map(|v| {
-                let mut sig = v.to_vec();
-                if let proc_macro2::TokenTree::Ident(ident) = sig.remove(0) {
-                    Some((ident.to_string(), sig))
+    }
+    Some(())
+}
+
+// rbdef attributeの key = value のオプション
+pub struct RbdefOption {
+    pub key: proc_macro2::Ident,
+    pub value: proc_macro2::TokenStream,
+}
+
+impl RbdefOption {
+    const KEYS: &'static [&'static str] = &["alias", "name", "visibility"];
+    // 値を持たないオプション
+    const FLAGS: &'static [&'static str] = &["singleton"];
+
+    fn is_option(segment: &[proc_macro2::TokenTree]) -> bool {
+        match segment {
+            // singleton という名前のメソッドは name = "singleton" で書く
+            [proc_macro2::TokenTree::Ident(key)] => Self::FLAGS.contains(&key.to_string().as_str()),
+            _ => Self::key(segment).is_some_and(|key| Self::KEYS.contains(&key.to_string().as_str())),
+        }
+    }
+
+    // key = value の形をしているが、知らないkey
+    fn unknown_key(segment: &[proc_macro2::TokenTree]) -> Option<&proc_macro2::Ident> {
+        Self::key(segment).filter(|key| !Self::KEYS.contains(&key.to_string().as_str()))
+    }
+
+    fn key(segment: &[proc_macro2::TokenTree]) -> Option<&proc_macro2::Ident> {
+        match segment {
+            // visibility=(v) のようなセッターはオプションではない
+            [_, _, proc_macro2::TokenTree::Group(g)] if g.delimiter() == proc_macro2::Delimiter::Parenthesis => None,
+            [proc_macro2::TokenTree::Ident(key), proc_macro2::TokenTree::Punct(p), _, ..] => {
+                if p.as_char() == '=' && p.spacing() == proc_macro2::Spacing::Alone {
+                    Some(key)
                 } else {
-                    //unreachable!("The first of def_signature is not Ident at def_signature_map.")
                     None
                 }
-            })
-            .into_iter()
-            .collect()
+            },
+            _ => None,
+        }
     }
+}
 
-    fn is_comma(token: &proc_macro2::TokenTree) -> bool {
-        if let proc_macro2::TokenTree::Punct(p) = token {
-            if p.as_char() == ',' {
-                return true;
+impl From<&[proc_macro2::TokenTree]> for RbdefOption {
+    fn from(segment: &[proc_macro2::TokenTree]) -> Self {
+        if let proc_macro2::TokenTree::Ident(key) = &segment[0] {
+            Self {
+                key: key.clone(),
+                value: proc_macro2::TokenStream::from_iter(segment.iter().skip(2).cloned()),
             }
+        } else {
+            unreachable!("RbdefOption::is_option checks the key")
         }
-        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Rbdef;
+
+    fn attribute_errors(item: syn::ImplItemMethod) -> Vec<String> {
+        let attr = item.attrs[0].clone();
+        Rbdef::new(item, attr)
+            .method_info(&[])
+            .attribute_errors
+            .iter()
+            .map(|e| e.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn accepts_options_around_the_name() {
+        let errors = attribute_errors(syn::parse_quote! {
+            #[rbdef(visibility = private, size?(list), alias = "length?", singleton)]
+            fn size(list: Array) -> Fixnum {}
+        });
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+
+    #[test]
+    fn rejects_unknown_flags() {
+        let errors = attribute_errors(syn::parse_quote! {
+            #[rbdef(size, sigleton)]
+            fn size() -> Fixnum {}
+        });
+        assert_eq!(errors, vec!["unknown rbdef flag `sigleton`. Expected one of singleton."]);
+    }
+
+    #[test]
+    fn rejects_unknown_options() {
+        let errors = attribute_errors(syn::parse_quote! {
+            #[rbdef(foo, visiblity = private)]
+            fn foo() -> Fixnum {}
+        });
+        assert_eq!(errors, vec!["unknown rbdef option `visiblity`. Expected one of alias, name, visibility."]);
+    }
+
+    #[test]
+    fn rejects_duplicate_options() {
+        let errors = attribute_errors(syn::parse_quote! {
+            #[rbdef(name = "a", name = "b", visibility = private, visibility = public, alias = "c", alias = "d")]
+            fn foo() -> Fixnum {}
+        });
+        assert_eq!(errors, vec!["duplicate rbdef option `name`.", "duplicate rbdef option `visibility`."]);
+    }
+
+    #[test]
+    fn rejects_a_second_name_and_signature() {
+        let errors = attribute_errors(syn::parse_quote! {
+            #[rbdef(foo(a), bar(b))]
+            fn foo(a: Fixnum, b: Fixnum) -> Fixnum {}
+        });
+        assert_eq!(errors, vec!["rbdef takes only one method name and signature."]);
     }
 }
@@ -1,6 +1,6 @@
 use super::rbdef::Rbdef;
-use super::method::{Method, MethodKind};
-use super::DEFINED_CLASSES;
+use super::method::{Method, MethodKind, Visibility};
+use super::{DEFINED_CLASSES, DEFINED_METHODS};
 
 pub struct Rbmethods {
     item: syn::ItemImpl,
@@ -46,11 +46,11 @@ impl Rbmethods {
 
         let method_structs_and_impls = self.method_structs_and_impls(&self.class_name, &rutie_class, &methods);
 
-        let item_impl = self.item.clone();
+        let item_impl = self.item_impl(&methods);
         let gen = quote::quote! {
             #item_impl
 
-            use rutie_attr_backend::FromArgWithDefault;
+            use rutie_attr_backend::{FromArg as _, FromArgWithDefault as _, FromArgWithKeyAndDefault as _, FromSplatArg as _, FromDoubleSplatArg as _, FromBlockArg as _};
             #method_structs_and_impls
 
             #extern_fns
@@ -61,10 +61,12 @@ impl Rbmethods {
     }
 
     fn validate(&self, methods: &[Method]) -> syn::Result<()> {
-        let errors = methods
+        self.validate_single_impl()?;
+        let mut errors = methods
             .iter()
             .filter_map(|method| method.validate().err())
             .collect::<Vec<syn::Error>>();
+        errors.extend(self.validate_duplicate_names(methods));
 
         let mut errors = errors.iter();
         if let Some(e) = errors.next() {
@@ -78,7 +80,57 @@ impl Rbmethods {
         }
     }
 
+    // Init_Fooはクラスごとに1つなので、#[rbmethods] のimplもクラスごとに1つだけ書ける
+    // 同じimplをもう一度展開したとき (rust-analyzerなど) は、関数名が重なるのでエラーにしない
+    fn validate_single_impl(&self) -> syn::Result<()> {
+        let fn_names = self
+            .item
+            .items
+            .iter()
+            .filter_map(|item| if let syn::ImplItem::Method(m) = item { Some(m.sig.ident.to_string()) } else { None })
+            .collect::<Vec<String>>();
+        let mut defined = DEFINED_METHODS.lock().unwrap();
+        if let Some(first) = defined.get(&self.class_name.to_string()) {
+            if !first.is_empty() && !fn_names.iter().any(|name| first.contains(name)) {
+                let message = format!(
+                    "#[rbmethods] is already used on another impl of `{}`. Put all rbdef methods of a class in one #[rbmethods] impl, because it defines Init_{}.",
+                    self.class_name, self.class_name,
+                );
+                return Err(syn::Error::new_spanned(&self.item.self_ty, message));
+            }
+        }
+        defined.insert(self.class_name.to_string(), fn_names);
+        Ok(())
+    }
+
+    // 同じRubyのメソッド名を複数のrbdefで登録すると後のものだけが残るのでエラーにする
+    // インスタンスメソッドと特異メソッドは別々にチェックする
+    fn validate_duplicate_names(&self, methods: &[Method]) -> Vec<syn::Error> {
+        let mut defined: Vec<(MethodKind, String, proc_macro2::TokenStream)> = Vec::new();
+        let mut errors = Vec::new();
+        for m in methods.iter() {
+            for (name, tokens) in m.spanned_ruby_names() {
+                for kind in m.namespaces() {
+                    let first = defined.iter().find(|(k, n, _)| *k == kind && *n == name);
+                    if let Some((_, _, first)) = first {
+                        let message = format!("method `{}` is defined more than once in this impl.", name);
+                        errors.push(syn::Error::new_spanned(first, format!("first definition of `{}` is here.", name)));
+                        errors.push(syn::Error::new_spanned(&tokens, message));
+                        // module_functionで両方の名前空間に重なっても1回だけ報告する
+                        break;
+                    }
+                    defined.push((kind, name.clone(), tokens.clone()));
+                }
+            }
+        }
+        errors
+    }
+
     fn class_attributes(&self) -> Vec<String> {
+        self.class_fields().into_iter().map(|(name, _)| name).collect()
+    }
+
+    fn class_fields(&self) -> Vec<(String, String)> {
         DEFINED_CLASSES
             .lock()
             .unwrap()
@@ -123,6 +175,23 @@ impl Rbmethods {
                 }
             };
             imethods.push(item_fn);
+
+            // 固定の引数の個数で登録するメソッドは、Rubyから個別の引数で呼ばれるので可変長引数の関数に渡し直す
+            if let Some(arity) = m.fixed_arity() {
+                let fixed_fn_name = self.extern_fixed_arity_fn_name(&m.fn_name);
+                let args = (0..arity).map(|i| quote::format_ident!("arg{}", i)).collect::<Vec<syn::Ident>>();
+                let item_fn: syn::ItemFn = syn::parse_quote! {
+                    #[allow(non_snake_case)]
+                    pub extern fn #fixed_fn_name(
+                        _rtself: #rutie_class,
+                        #(#args: rutie::AnyObject),*
+                    ) -> rutie::AnyObject {
+                        let argv: [rutie::AnyObject; #arity] = [#(#args),*];
+                        #new_fn_name(argv.len() as rutie::types::Argc, argv.as_ptr(), _rtself)
+                    }
+                };
+                imethods.push(item_fn);
+            }
         }
         imethods
     }
@@ -139,17 +208,24 @@ impl Rbmethods {
         }
 
         for m in methods.iter() {
-            let def_name = &m.def_name();
-            let new_fn_name = self.extern_impl_fn_name(&m.fn_name);
-            let stmt: syn::Stmt = match m.kind {
-                MethodKind::Instance => syn::parse_quote! {
-                    klass.def(#def_name, #new_fn_name);
-                },
-                MethodKind::Static => syn::parse_quote! {
-                    klass.def_self(#def_name, #new_fn_name);
-                },
-            };
-            block.stmts.push(stmt);
+            // 別名も同じextern fnで登録する
+            for name in m.ruby_names().iter() {
+                match m.visibility {
+                    Visibility::Public => {
+                        block.stmts.push(self.define_method_stmt(m, &m.kind, name));
+                    },
+                    Visibility::Private | Visibility::Protected => {
+                        block.stmts.push(self.define_method_stmt(m, &m.kind, name));
+                        block.stmts.push(self.visibility_stmt(m, &m.kind, name));
+                    },
+                    Visibility::ModuleFunction => {
+                        // Foo.name と、privateなインスタンスメソッドの name の両方を定義する
+                        block.stmts.push(self.define_method_stmt(m, &MethodKind::Static, name));
+                        block.stmts.push(self.define_method_stmt(m, &MethodKind::Instance, name));
+                        block.stmts.push(self.visibility_stmt(m, &MethodKind::Instance, name));
+                    },
+                }
+            }
         }
 
         //let rutie_class_name = format!("Rutie{}", &self.class_name);
@@ -166,6 +242,53 @@ impl Rbmethods {
         }
     }
 
+    // 名前はUTF-8でinternして登録する
+    fn define_method_stmt(&self, m: &Method, kind: &MethodKind, def_name: &str) -> syn::Stmt {
+        // Method#arityが正しくなるように、固定の引数の個数で呼ぶ関数は個数を指定して登録する
+        // それ以外は -1 で argc, argv を受け取る関数を登録する
+        let (fn_name, arity) = match m.fixed_arity() {
+            Some(arity) => (self.extern_fixed_arity_fn_name(&m.fn_name), arity as i32),
+            None => (self.extern_impl_fn_name(&m.fn_name), -1),
+        };
+        let define: syn::Path = match kind {
+            MethodKind::Instance => syn::parse_quote! { rutie_attr_backend::define_method },
+            MethodKind::Static => syn::parse_quote! { rutie_attr_backend::define_singleton_method },
+        };
+        syn::parse_quote! {
+            unsafe {
+                #define(klass, #def_name, #fn_name as rutie::types::CallbackPtr, #arity);
+            }
+        }
+    }
+
+    // 定義したメソッドをRubyの private / protected と同じように呼び出し元を制限する
+    fn visibility_stmt(&self, m: &Method, kind: &MethodKind, def_name: &str) -> syn::Stmt {
+        let visibility = match m.visibility {
+            Visibility::Protected => "protected",
+            _ => "private",
+        };
+        let target: syn::Expr = match kind {
+            MethodKind::Instance => syn::parse_quote! { klass },
+            MethodKind::Static => syn::parse_quote! { &rutie::Object::singleton_class(klass) },
+        };
+        syn::parse_quote! {
+            rutie_attr_backend::set_visibility(#target, #visibility, #def_name);
+        }
+    }
+
+    // フィールドの構造体で呼ぶメソッドは、元のメソッドが使われなくなる
+    fn item_impl(&self, methods: &[Method]) -> syn::ItemImpl {
+        let mut item = self.item.clone();
+        for impl_item in item.items.iter_mut() {
+            if let syn::ImplItem::Method(m) = impl_item {
+                if methods.iter().any(|method| method.fn_name == m.sig.ident && method.uses_fields_struct()) {
+                    m.attrs.push(syn::parse_quote! { #[allow(dead_code)] });
+                }
+            }
+        }
+        item
+    }
+
     // impl内の各メソッドをパースしてMethodのVecを作る
     fn parse_rbdefs(&self) -> Vec<Method> {
         self.item
@@ -186,12 +309,17 @@ impl Rbmethods {
         item
             .attrs
             .iter()
-            .find(|attr| attr.path.get_ident().unwrap() == "rbdef" ) // rbdefアトリビュートが設定されているメソッドのみを対象にする
-            .map(|attr| Rbdef::new(item.clone(), attr.clone()).method_info())
+            .find(|attr| attr.path.is_ident("rbdef") || attr.path.is_ident("rbdefself")) // rbdef, rbdefselfアトリビュートが設定されているメソッドのみを対象にする
+            .map(|attr| Rbdef::new(item.clone(), attr.clone()).method_info(&self.class_fields()))
     }
 
     // Rubyで読み込む際に渡すシンボル名
     fn extern_impl_fn_name(&self, fn_name: &proc_macro2::Ident) -> syn::Ident {
         quote::format_ident!("rutie_{}_{}", self.class_name, fn_name)
     }
+
+    // 固定の引数の個数で登録するときの関数名
+    fn extern_fixed_arity_fn_name(&self, fn_name: &proc_macro2::Ident) -> syn::Ident {
+        quote::format_ident!("rutie_{}_{}_fixed", self.class_name, fn_name)
+    }
 }
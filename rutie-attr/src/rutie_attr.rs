@@ -2,15 +2,26 @@ pub mod rbclass;
 pub mod rbdef;
 pub mod rbmethods;
 mod method;
+mod method_name;
 mod argument;
+mod signature;
 mod util;
 
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
 use std::sync::Mutex;
 
+// 構造体のフィールドの (フィールド名, 型のトークン列)
+type ClassFields = Vec<(String, String)>;
+
 // rbclassで解析した構造体のフィールド情報をrbmethodsで使うためのグローバル変数
-static DEFINED_CLASSES: Lazy<Mutex<HashMap<String, Vec<String>>>> = Lazy::new(|| {
+static DEFINED_CLASSES: Lazy<Mutex<HashMap<String, ClassFields>>> = Lazy::new(|| {
+    let m = HashMap::new();
+    Mutex::new(m)
+});
+
+// rbmethodsを展開したクラスと、そのimplに書かれた関数名
+static DEFINED_METHODS: Lazy<Mutex<HashMap<String, Vec<String>>>> = Lazy::new(|| {
     let m = HashMap::new();
     Mutex::new(m)
 });
@@ -0,0 +1,198 @@
+use super::argument::ArgumentKind;
+use super::util::combined_errors;
+use std::iter::FromIterator;
+
+// rbdef attributeに書かれたRubyの引数1つ分
+#[derive(Debug)]
+pub struct Parameter {
+    pub name: proc_macro2::Ident,
+    pub kind: ArgumentKind,
+    pub default_value: Option<proc_macro2::TokenStream>,
+    // エラーのspan用
+    pub tokens: proc_macro2::TokenStream,
+}
+
+// Rubyの引数の並び順。後ろの区分の引数の後に前の区分の引数は書けない
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+enum Section {
+    Required,
+    Optional,
+    Rest,
+    Post,
+    Keyword,
+    KeywordRest,
+    Block,
+}
+
+impl Section {
+    fn describe(&self) -> &'static str {
+        match self {
+            Self::Required => "required",
+            Self::Optional => "optional",
+            Self::Rest => "rest (*)",
+            Self::Post => "trailing required",
+            Self::Keyword => "keyword",
+            Self::KeywordRest => "keyword rest (**)",
+            Self::Block => "block (&)",
+        }
+    }
+
+    // 1つしか書けない区分
+    fn is_single(&self) -> bool {
+        matches!(self, Self::Rest | Self::KeywordRest | Self::Block)
+    }
+}
+
+// rbdef attributeの引数リスト
+// def name(a, b = 1, *rest, c, d:, e: 2, **opts, &blk)
+#[derive(Debug)]
+pub struct Signature {
+    pub parameters: Vec<Parameter>,
+    errors: Vec<syn::Error>,
+}
+
+impl Signature {
+    pub fn parse(group: proc_macro2::Group) -> Self {
+        let mut parameters = Vec::new();
+        let mut errors = Vec::new();
+        let trees = group.stream().into_iter().collect::<Vec<proc_macro2::TokenTree>>();
+        for tokens in trees.split(Self::is_comma).filter(|v| !v.is_empty()) {
+            match Self::parse_parameter(tokens) {
+                Ok(parameter) => parameters.push(parameter),
+                Err(e) => errors.push(e),
+            }
+        }
+        Self { parameters, errors }
+    }
+
+    fn parse_parameter(tokens: &[proc_macro2::TokenTree]) -> syn::Result<Parameter> {
+        use proc_macro2::TokenTree::{Ident, Punct};
+
+        let stream = proc_macro2::TokenStream::from_iter(tokens.to_vec());
+        let parameter = |name: &proc_macro2::Ident, kind, default_value| Parameter {
+            name: name.clone(),
+            kind,
+            default_value,
+            tokens: stream.clone(),
+        };
+        let rest = |tokens: &[proc_macro2::TokenTree]| proc_macro2::TokenStream::from_iter(tokens.to_vec());
+
+        match tokens {
+            // a
+            [Ident(name)] => Ok(parameter(name, ArgumentKind::Arg, None)),
+            // a = 1
+            [Ident(name), Punct(p), value @ ..] if p.as_char() == '=' => {
+                if value.is_empty() {
+                    return Err(syn::Error::new_spanned(&stream, "expected a default value after `=`."));
+                }
+                Ok(parameter(name, ArgumentKind::DArg, Some(rest(value))))
+            },
+            // c: または c: 1
+            [Ident(name), Punct(p), value @ ..] if p.as_char() == ':' && !Self::is_path_separator(p, value) => {
+                let default_value = if value.is_empty() { None } else { Some(rest(value)) };
+                Ok(parameter(name, ArgumentKind::KwArg, default_value))
+            },
+            // *rest
+            [Punct(p), Ident(name)] if p.as_char() == '*' => Ok(parameter(name, ArgumentKind::Splat, None)),
+            // **opts
+            [Punct(p1), Punct(p2), Ident(name)] if p1.as_char() == '*' && p2.as_char() == '*' => {
+                Ok(parameter(name, ArgumentKind::DoubleSplat, None))
+            },
+            // &blk
+            [Punct(p), Ident(name)] if p.as_char() == '&' => Ok(parameter(name, ArgumentKind::Block, None)),
+            _ => Err(syn::Error::new_spanned(
+                &stream,
+                "expected a Ruby parameter: `a`, `a = 1`, `*rest`, `c:`, `c: 1`, `**opts` or `&blk`.",
+            )),
+        }
+    }
+
+    // a::B のような :: はキーワード引数ではない
+    fn is_path_separator(p: &proc_macro2::Punct, value: &[proc_macro2::TokenTree]) -> bool {
+        p.spacing() == proc_macro2::Spacing::Joint
+            && matches!(value.first(), Some(proc_macro2::TokenTree::Punct(next)) if next.as_char() == ':')
+    }
+
+    fn is_comma(token: &proc_macro2::TokenTree) -> bool {
+        if let proc_macro2::TokenTree::Punct(p) = token {
+            if p.as_char() == ',' {
+                return true;
+            }
+        }
+        false
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Parameter> {
+        self.parameters.iter().find(|p| p.name == name)
+    }
+
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.parameters.iter().position(|p| p.name == name)
+    }
+
+    // シグネチャに書かれていない引数は、Rustでの宣言順に先頭の必須引数にする
+    pub fn prepend_required<'a>(&mut self, names: impl Iterator<Item = &'a proc_macro2::Ident>) {
+        let leading = names
+            .filter(|name| self.get(&name.to_string()).is_none())
+            .map(|name| Parameter {
+                name: name.clone(),
+                kind: ArgumentKind::Arg,
+                default_value: None,
+                tokens: quote::quote! { #name },
+            })
+            .collect::<Vec<Parameter>>();
+        self.parameters.splice(0..0, leading);
+    }
+
+    // Rubyと同じ並び順の規則と、引数名の重複をチェックする
+    pub fn validate(&self) -> syn::Result<()> {
+        let mut errors = self.errors.clone();
+        let mut names: Vec<&proc_macro2::Ident> = Vec::new();
+        let mut last: Option<Section> = None;
+
+        for parameter in self.parameters.iter() {
+            if names.contains(&&parameter.name) {
+                errors.push(syn::Error::new_spanned(&parameter.tokens, "duplicated argument name."));
+            }
+            names.push(&parameter.name);
+
+            let section = match parameter.kind {
+                // 省略可能な引数や *rest の後ろの必須引数は後置の引数
+                ArgumentKind::Arg => match last {
+                    Some(s) if s >= Section::Optional => Section::Post,
+                    _ => Section::Required,
+                },
+                ArgumentKind::DArg => Section::Optional,
+                ArgumentKind::Splat => Section::Rest,
+                ArgumentKind::KwArg => Section::Keyword,
+                ArgumentKind::DoubleSplat => Section::KeywordRest,
+                ArgumentKind::Block => Section::Block,
+            };
+
+            if let Some(l) = last {
+                if section < l {
+                    let message = format!(
+                        "{} parameter `{}` must come before {} parameters.",
+                        section.describe(),
+                        parameter.name,
+                        l.describe(),
+                    );
+                    errors.push(syn::Error::new_spanned(&parameter.tokens, message));
+                    continue;
+                }
+                if section == l && section.is_single() {
+                    let message = format!("only one {} parameter is allowed.", section.describe());
+                    errors.push(syn::Error::new_spanned(&parameter.tokens, message));
+                    continue;
+                }
+            }
+            last = Some(section);
+        }
+
+        if let Some(e) = combined_errors(errors) {
+            Err(e)
+        } else {
+            Ok(())
+        }
+    }
+}
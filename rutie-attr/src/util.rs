@@ -18,3 +18,8 @@ pub fn uppercase_first_letter(s: &str) -> String {
         Some(f) => f.to_uppercase().chain(c).collect(),
     }
 }
+
+// rbclassのフィールドをインスタンス変数から読む関数の名前
+pub fn field_reader_ident(field: &proc_macro2::Ident) -> proc_macro2::Ident {
+    quote::format_ident!("{}_field", field)
+}